@@ -1,13 +1,37 @@
-use std::sync::Mutex;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use std::path::Path;
-use tauri::{AppHandle, State};
+use tauri::{AppHandle, Manager, State};
 use tauri_plugin_store::StoreExt;
-use tokio::sync::mpsc;
-use crate::AppState;
-use crate::modules::types::{RepositoryInfo, CloneProgressEvent, CloneCompleteEvent, TagInfo};
+use tokio::sync::{mpsc, watch, Semaphore};
+use tokio::task::JoinSet;
+use crate::{AppState, CloneJob};
+use crate::modules::backend::{self, GitBackend};
+use crate::modules::channels::{self, ChannelResolver};
+use crate::modules::credentials;
+use crate::modules::types::{RepositoryInfo, CloneProgressEvent, CloneCompleteEvent, CredentialRequestEvent, CredentialSummary, TagInfo, PendingClone, InterruptedClone, CloneOptions, GitBackendKind, ChannelRule, TagSortMode, TagQuery, TagPage, TagDetails, Changelog, SwitchPreflight, SwitchMode, BackupInfo, CleanPolicy, HeadInfo, HeadRef, RepositoryStatus, AutoRefresh, NewVersionAvailableEvent, FollowChannel, FollowRecord, AutoSwitchEvent, Pin, PinDrift, HistoryEntry, HistoryPoint, SwitchSource, RefreshSettings, RepositoryRefreshedEvent};
+use crate::modules::error::{CommandError, GitError};
 use crate::modules::git::{Git, CloneProgress};
 use tauri_specta::Event;
 
+/// How many versions a repository keeps for its dropdown; `query_tags` reaches the rest.
+const VERSION_LIST_LIMIT: usize = 10;
+
+/// Most commits listed on each side of a changelog.
+const CHANGELOG_LIMIT: usize = 500;
+
+/// Most entries listed per kind in a switch preflight.
+const PREFLIGHT_LIST_LIMIT: usize = 200;
+
+/// How many entries `RepositoryInfo::follow_log` keeps.
+const FOLLOW_LOG_LIMIT: usize = 100;
+
+/// How many clones and switches `AppState::version_history` keeps per repository.
+const HISTORY_LIMIT: usize = 200;
+
+/// Returned when a repository's `RepoGuard` is already held.
+const REPOSITORY_BUSY: &str = "A refresh or version switch is already running for this repository";
+
 #[tauri::command]
 #[specta::specta]
 pub fn get_root_path(state: State<'_, Mutex<AppState>>) -> String {
@@ -26,6 +50,300 @@ pub fn set_root_path(app: AppHandle, state: State<'_, Mutex<AppState>>, path: St
     path
 }
 
+fn git_backend(state: &State<'_, Mutex<AppState>>) -> Arc<dyn GitBackend> {
+    state.lock().unwrap().git_backend.clone()
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_git_backend(state: State<'_, Mutex<AppState>>) -> GitBackendKind {
+    git_backend(&state).kind()
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn set_git_backend(app: AppHandle, state: State<'_, Mutex<AppState>>, kind: GitBackendKind) -> Result<GitBackendKind, String> {
+    let backend = backend::backend_for(kind)?;
+    {
+        let mut st = state.lock().unwrap();
+        st.git_backend = backend;
+    }
+    save_state(app, state).ok();
+    Ok(kind)
+}
+
+/// Backends usable on this machine, so settings can warn when git is missing from PATH.
+#[tauri::command]
+#[specta::specta]
+pub async fn get_available_git_backends() -> Vec<GitBackendKind> {
+    backend::available_backends().await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_refresh_settings(state: State<'_, Mutex<AppState>>) -> RefreshSettings {
+    state.lock().unwrap().refresh_settings
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn set_refresh_settings(app: AppHandle, state: State<'_, Mutex<AppState>>, settings: RefreshSettings) -> Result<RefreshSettings, String> {
+    if settings.concurrency == 0 {
+        return Err("Concurrency must be at least 1".to_string());
+    }
+    {
+        let mut st = state.lock().unwrap();
+        st.refresh_settings = settings;
+    }
+    save_state(app, state).ok();
+    Ok(settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_credentials() -> Result<Vec<CredentialSummary>, String> {
+    credentials::list()
+}
+
+/// Saves an HTTPS username and token for `host` in the encrypted vault, replacing any previous one.
+#[tauri::command]
+#[specta::specta]
+pub fn save_credential(host: String, username: String, token: String) -> Result<Vec<CredentialSummary>, String> {
+    credentials::store(&host, &username, &token)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn delete_credential(host: String) -> Result<Vec<CredentialSummary>, String> {
+    credentials::remove(&host)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_channel_rules(state: State<'_, Mutex<AppState>>) -> Vec<ChannelRule> {
+    state.lock().unwrap().channel_rules.clone()
+}
+
+/// Replaces the global channel rules. Repositories pick them up on their next refresh.
+#[tauri::command]
+#[specta::specta]
+pub fn set_channel_rules(app: AppHandle, state: State<'_, Mutex<AppState>>, rules: Vec<ChannelRule>) -> Result<Vec<ChannelRule>, String> {
+    channels::validate(&rules)?;
+    state.lock().unwrap().channel_rules = rules.clone();
+    save_state(app, state).ok();
+    Ok(rules)
+}
+
+/// Gives a repository its own channel rules, or makes it follow the global ones again with `None`.
+#[tauri::command]
+#[specta::specta]
+pub fn set_repository_channel_rules(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    repo_id: u32,
+    rules: Option<Vec<ChannelRule>>,
+) -> Result<RepositoryInfo, String> {
+    if let Some(rules) = &rules {
+        channels::validate(rules)?;
+    }
+
+    let repo = {
+        let mut state = state.lock().unwrap();
+        let repo = state.local_repositories
+            .iter_mut()
+            .find(|r| r.id == repo_id)
+            .ok_or("Repository not found")?;
+        repo.channel_rules = rules;
+        repo.clone()
+    };
+    save_state(app, state).ok();
+    Ok(repo)
+}
+
+/// Changes how a repository's tags are ordered. Takes effect on its next refresh.
+#[tauri::command]
+#[specta::specta]
+pub fn set_tag_sort(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    repo_id: u32,
+    sort: TagSortMode,
+) -> Result<RepositoryInfo, String> {
+    let repo = {
+        let mut state = state.lock().unwrap();
+        let repo = state.local_repositories
+            .iter_mut()
+            .find(|r| r.id == repo_id)
+            .ok_or("Repository not found")?;
+        repo.tag_sort = sort;
+        repo.clone()
+    };
+    save_state(app, state).ok();
+    Ok(repo)
+}
+
+/// Sets what `change_version` cleans from the working tree after a switch.
+#[tauri::command]
+#[specta::specta]
+pub fn set_clean_policy(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    repo_id: u32,
+    policy: CleanPolicy,
+) -> Result<RepositoryInfo, String> {
+    let repo = {
+        let mut state = state.lock().unwrap();
+        let repo = state.local_repositories
+            .iter_mut()
+            .find(|r| r.id == repo_id)
+            .ok_or("Repository not found")?;
+        repo.clean_policy = CleanPolicy {
+            preserve: policy.preserve.iter().map(|p| p.trim().to_string()).filter(|p| !p.is_empty()).collect(),
+            ..policy
+        };
+        repo.clone()
+    };
+    save_state(app, state).ok();
+    Ok(repo)
+}
+
+/// Sets how often the background scheduler refreshes a repository and which
+/// channels it reports new versions for.
+#[tauri::command]
+#[specta::specta]
+pub fn set_auto_refresh(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    repo_id: u32,
+    settings: AutoRefresh,
+) -> Result<RepositoryInfo, String> {
+    if settings.interval_minutes == Some(0) {
+        return Err("Refresh interval must be at least one minute".to_string());
+    }
+    let repo = {
+        let mut state = state.lock().unwrap();
+        let repo = state.local_repositories
+            .iter_mut()
+            .find(|r| r.id == repo_id)
+            .ok_or("Repository not found")?;
+        repo.auto_refresh = settings;
+        repo.clone()
+    };
+    save_state(app, state).ok();
+    Ok(repo)
+}
+
+/// Puts a repository in follow mode for `follow.channel`, or takes it out with `None`.
+/// The switch itself happens on the next refresh.
+#[tauri::command]
+#[specta::specta]
+pub fn set_follow_channel(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    repo_id: u32,
+    follow: Option<FollowChannel>,
+) -> Result<RepositoryInfo, String> {
+    if follow.as_ref().is_some_and(|f| f.channel.trim().is_empty()) {
+        return Err("Choose a channel to follow".to_string());
+    }
+    let repo = {
+        let mut state = state.lock().unwrap();
+        let repo = state.local_repositories
+            .iter_mut()
+            .find(|r| r.id == repo_id)
+            .ok_or("Repository not found")?;
+        repo.follow = follow;
+        repo.clone()
+    };
+    save_state(app, state).ok();
+    Ok(repo)
+}
+
+/// Pins a repository to the tag or branch it has checked out, recording why and by whom.
+#[tauri::command]
+#[specta::specta]
+pub async fn pin_repository(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    repo_id: u32,
+    reason: String,
+) -> Result<RepositoryInfo, CommandError> {
+    let repo_path = {
+        let state = state.lock().unwrap();
+        state.local_repositories.iter().find(|r| r.id == repo_id).map(|r| r.path.clone())
+    };
+    let repo_path = repo_path.ok_or("Repository not found")?;
+
+    let head = git_backend(&state).head(&repo_path, &[]).await?;
+    let tag = match head.reference {
+        HeadRef::Tag { name } => name,
+        HeadRef::Branch { name, .. } => format!("{}{}", channels::BRANCH_PREFIX, name),
+        HeadRef::Detached { .. } => return Err("Check out a tag or branch before pinning".into()),
+    };
+    let pinned_by = match Git::user_name(&repo_path).await {
+        Some(name) => name,
+        None => std::env::var("USER").or_else(|_| std::env::var("USERNAME")).unwrap_or_default(),
+    };
+    let pin = Pin {
+        tag,
+        commit: head.commit,
+        reason: reason.trim().to_string(),
+        pinned_by,
+        pinned_at: chrono::Local::now().to_rfc3339(),
+    };
+
+    let repo = {
+        let mut state = state.lock().unwrap();
+        let repo = state.local_repositories
+            .iter_mut()
+            .find(|r| r.id == repo_id)
+            .ok_or("Repository not found")?;
+        repo.pin = Some(pin);
+        repo.clone()
+    };
+    save_state(app, state).ok();
+    Ok(repo)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn unpin_repository(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    repo_id: u32,
+) -> Result<RepositoryInfo, String> {
+    let repo = {
+        let mut state = state.lock().unwrap();
+        let repo = state.local_repositories
+            .iter_mut()
+            .find(|r| r.id == repo_id)
+            .ok_or("Repository not found")?;
+        repo.pin = None;
+        if let Some(status) = &mut repo.status {
+            status.pin_drift = None;
+        }
+        repo.clone()
+    };
+    save_state(app, state).ok();
+    Ok(repo)
+}
+
+/// Passes `error` through, after asking the frontend for credentials when the
+/// remote turned git away for lack of them.
+fn with_credential_request(app: &AppHandle, repo_name: &str, remote_url: &str, error: GitError) -> GitError {
+    if let GitError::AuthRequired { .. } = error {
+        if let (Some(host), Some(kind)) = (credentials::host_of(remote_url), credentials::credential_kind(remote_url)) {
+            CredentialRequestEvent {
+                repo_name: repo_name.to_string(),
+                remote_url: remote_url.to_string(),
+                host,
+                kind,
+            }.emit(app).ok();
+        }
+    }
+    error
+}
+
 #[tauri::command]
 #[specta::specta]
 pub fn validate_repo_name(name: String) -> Result<bool, String> {
@@ -53,66 +371,138 @@ pub async fn clone_repository(
     state: State<'_, Mutex<AppState>>,
     remote_url: String,
     project_name: String,
-) -> Result<RepositoryInfo, String> {
+    options: Option<CloneOptions>,
+) -> Result<RepositoryInfo, CommandError> {
+    let options = options.unwrap_or_default();
     let root_path = {
         let state = state.lock().unwrap();
         state.path_root.clone()
     };
 
     if root_path.is_empty() {
-        return Err("Root path is not set. Please set it in Settings.".to_string());
+        return Err("Root path is not set. Please set it in Settings.".into());
     }
 
     let valid = project_name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
     if !valid || project_name.is_empty() {
-        return Err("Invalid repository name".to_string());
+        return Err("Invalid repository name".into());
     }
 
     let final_path = format!("{}/{}", root_path, project_name);
     if Path::new(&final_path).exists() {
-        return Err(format!("Directory '{}' already exists", project_name));
+        return Err(format!("Directory '{}' already exists", project_name).into());
     }
 
     let temp_path = format!("{}/.tmp_{}", root_path, project_name);
+    let is_running = state.lock().unwrap().clone_jobs.contains_key(&project_name);
+    if Path::new(&temp_path).exists() && !is_running {
+        return Err(format!("An interrupted clone of '{}' exists. Resume or discard it first.", project_name).into());
+    }
+
+    let job = PendingClone {
+        name: project_name.clone(),
+        remote_url: remote_url.clone(),
+        temp_path: temp_path.clone(),
+        started_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        options,
+    };
+    let cancel_rx = begin_clone_job(&app, &state, job.clone())?;
 
     println!("[DEBUG] Starting clone: {} -> {}", remote_url, temp_path);
 
+    emit_clone_stage(&app, &project_name, 0, "Starting...");
+
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+    let (tx, progress_task) = spawn_progress_forwarder(&app, &project_name);
+
+    let backend = git_backend(&state);
+    println!("[DEBUG] Calling clone_with_progress on the {:?} backend", backend.kind());
+    let clone_result = backend.clone_with_progress(&temp_path, &remote_url, &job.options, tx, cancel_rx).await;
+    println!("[DEBUG] clone_with_progress returned: {:?}", clone_result);
+    
+    progress_task.abort();
+
+    finish_clone(app, state, job, final_path, clone_result, false).await
+}
+
+/// Registers a running clone so it can be cancelled, and persists it as pending
+/// so an app restart can offer to resume it.
+fn begin_clone_job(
+    app: &AppHandle,
+    state: &State<'_, Mutex<AppState>>,
+    job: PendingClone,
+) -> Result<watch::Receiver<bool>, String> {
+    let (cancel_tx, cancel_rx) = watch::channel(false);
+    {
+        let mut state = state.lock().unwrap();
+        if state.clone_jobs.contains_key(&job.name) {
+            return Err(format!("'{}' is already being cloned", job.name));
+        }
+        state.clone_jobs.insert(job.name.clone(), CloneJob { cancel_tx });
+        state.pending_clones.retain(|p| p.name != job.name);
+        state.pending_clones.push(job);
+    }
+    save_state(app.clone(), state.clone()).ok();
+    Ok(cancel_rx)
+}
+
+fn emit_clone_stage(app: &AppHandle, repo_name: &str, progress: u32, message: &str) {
     CloneProgressEvent {
-        repo_name: project_name.clone(),
-        progress: 0,
-        message: "Starting...".to_string(),
+        repo_name: repo_name.to_string(),
+        progress,
+        message: message.to_string(),
         received_bytes: None,
         total_objects: None,
         received_objects: None,
         speed: None,
-    }.emit(&app).ok();
-
-    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        submodule: None,
+    }.emit(app).ok();
+}
 
+fn spawn_progress_forwarder(
+    app: &AppHandle,
+    repo_name: &str,
+) -> (mpsc::Sender<CloneProgress>, tokio::task::JoinHandle<()>) {
     let (tx, mut rx) = mpsc::channel::<CloneProgress>(100);
     
     let app_clone = app.clone();
-    let project_name_clone = project_name.clone();
+    let project_name_clone = repo_name.to_string();
     
     let progress_task = tokio::spawn(async move {
         while let Some(progress) = rx.recv().await {
             let message = match progress.stage.as_str() {
                 "Receiving" => {
                     if let (Some(bytes), Some(spd)) = (progress.received_bytes, &progress.speed) {
-                        let mb = bytes as f64 / (1024.0 * 1024.0);
+                        let mb = bytes / (1024.0 * 1024.0);
                         format!("Receiving objects... {:.1} MB | {}", mb, spd)
                     } else if let Some(bytes) = progress.received_bytes {
-                        let mb = bytes as f64 / (1024.0 * 1024.0);
+                        let mb = bytes / (1024.0 * 1024.0);
                         format!("Receiving objects... {:.1} MB", mb)
                     } else {
                         "Receiving objects...".to_string()
                     }
                 },
+                "LfsDownloading" => {
+                    if let (Some(bytes), Some(spd)) = (progress.received_bytes, &progress.speed) {
+                        let mb = bytes / (1024.0 * 1024.0);
+                        format!("Downloading LFS objects... {:.1} MB | {}", mb, spd)
+                    } else {
+                        "Downloading LFS objects...".to_string()
+                    }
+                },
+                "LfsCheckout" => "Checking out LFS objects...".to_string(),
                 "Resolving" => "Resolving deltas...".to_string(),
+                "Checkout" => "Checking out files...".to_string(),
                 "Compressing" => "Compressing objects...".to_string(),
                 "Counting" => "Counting objects...".to_string(),
+                "Submodule" => format!("Updating submodule {}...", progress.submodule.as_deref().unwrap_or_default()),
                 _ => progress.stage.clone(),
             };
+            let message = match (&progress.submodule, progress.stage.as_str()) {
+                (Some(submodule), stage) if stage != "Submodule" => format!("[{}] {}", submodule, message),
+                _ => message,
+            };
 
             CloneProgressEvent {
                 repo_name: project_name_clone.clone(),
@@ -122,221 +512,830 @@ pub async fn clone_repository(
                 total_objects: progress.total_objects,
                 received_objects: progress.received_objects,
                 speed: progress.speed,
+                submodule: progress.submodule,
             }.emit(&app_clone).ok();
         }
     });
 
-    println!("[DEBUG] Calling Git::clone_with_progress");
-    let clone_result = Git::clone_with_progress(&temp_path, &remote_url, tx).await;
-    println!("[DEBUG] Git::clone_with_progress returned: {}", clone_result);
-    
-    progress_task.abort();
+    (tx, progress_task)
+}
+
+/// Runs the explicit LFS download/checkout step after the working tree changed, reporting
+/// its progress as `CloneProgressEvent`s for `repo_name`. Does nothing for non-LFS repositories.
+async fn sync_lfs_content(app: &AppHandle, repo_name: &str, repo_path: &str, options: &CloneOptions) -> Result<(), GitError> {
+    if !Git::uses_lfs(repo_path) {
+        return Ok(());
+    }
 
-    if !clone_result {
-        if Path::new(&temp_path).exists() {
-            std::fs::remove_dir_all(&temp_path).ok();
+    let (tx, progress_task) = spawn_progress_forwarder(app, repo_name);
+    let (_cancel_tx, cancel_rx) = watch::channel(false);
+    let result = Git::lfs_fetch_and_checkout(repo_path, options, tx, cancel_rx).await;
+    progress_task.await.ok();
+    result
+}
+
+/// Updates submodules to the commits recorded by the current checkout, reporting
+/// per-submodule progress. Does nothing for repositories without submodules.
+async fn sync_submodules(app: &AppHandle, repo_name: &str, repo_path: &str, options: &CloneOptions) -> Result<(), GitError> {
+    if !Git::has_submodules(repo_path) {
+        return Ok(());
+    }
+
+    let (tx, progress_task) = spawn_progress_forwarder(app, repo_name);
+    let (_cancel_tx, cancel_rx) = watch::channel(false);
+    let result = Git::update_submodules(repo_path, options, tx, cancel_rx).await;
+    progress_task.await.ok();
+    result
+}
+
+/// Deletes a clone's temporary directory. Files the killed git processes had open can
+/// stay locked for a moment (Windows refuses to delete them), so it retries briefly.
+async fn remove_temp_dir(path: &str) {
+    let mut delays = [100, 250, 500, 1000].into_iter();
+    loop {
+        match std::fs::remove_dir_all(path) {
+            Ok(()) => return,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return,
+            Err(e) => match delays.next() {
+                Some(ms) => tokio::time::sleep(tokio::time::Duration::from_millis(ms)).await,
+                None => {
+                    println!("[finish_clone] Could not delete {}: {}", path, e);
+                    return;
+                }
+            },
+        }
+    }
+}
+
+/// Shared tail of `clone_repository` and `resume_clone`: clears the job, then either
+/// cleans up after a failed/cancelled transfer or moves the repo into place and registers it.
+/// A failed resume keeps its partial repository and pending record for another try;
+/// only `discard_interrupted_clone` deletes those.
+async fn finish_clone(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    job: PendingClone,
+    final_path: String,
+    clone_result: Result<(), GitError>,
+    resuming: bool,
+) -> Result<RepositoryInfo, CommandError> {
+    let PendingClone { name: project_name, remote_url, temp_path, options, .. } = job;
+    let keep_partial = resuming && clone_result.is_err();
+    {
+        let mut state = state.lock().unwrap();
+        state.clone_jobs.remove(&project_name);
+        if !keep_partial {
+            state.pending_clones.retain(|p| p.name != project_name);
+        }
+    }
+    save_state(app.clone(), state.clone()).ok();
+
+    if let Err(error) = clone_result {
+        if !keep_partial {
+            remove_temp_dir(&temp_path).await;
         }
+        let error = with_credential_request(&app, &project_name, &remote_url, error);
         CloneCompleteEvent {
             repo_name: project_name.clone(),
             success: false,
-            error_message: Some("Failed to clone repository".to_string()),
+            cancelled: matches!(error, GitError::Cancelled),
+            error_message: Some(error.to_string()),
         }.emit(&app).ok();
-        return Err("Failed to clone repository".to_string());
+        return Err(error.into());
     }
 
-    CloneProgressEvent {
-        repo_name: project_name.clone(),
-        progress: 60,
-        message: "Fetching tags...".to_string(),
-        received_bytes: None,
-        total_objects: None,
-        received_objects: None,
-        speed: None,
-    }.emit(&app).ok();
-
-    CloneProgressEvent {
-        repo_name: project_name.clone(),
-        progress: 80,
-        message: "Moving to final location...".to_string(),
-        received_bytes: None,
-        total_objects: None,
-        received_objects: None,
-        speed: None,
-    }.emit(&app).ok();
+    emit_clone_stage(&app, &project_name, 60, "Fetching tags...");
+    emit_clone_stage(&app, &project_name, 80, "Moving to final location...");
 
     if let Err(e) = std::fs::rename(&temp_path, &final_path) {
-        std::fs::remove_dir_all(&temp_path).ok();
+        remove_temp_dir(&temp_path).await;
         CloneCompleteEvent {
             repo_name: project_name.clone(),
             success: false,
+            cancelled: false,
             error_message: Some(format!("Failed to move repository: {}", e)),
         }.emit(&app).ok();
-        return Err(format!("Failed to move repository: {}", e));
+        return Err(format!("Failed to move repository: {}", e).into());
     }
 
-    let branch = Git::current_branch(&final_path).await.unwrap_or("main".to_string());
+    let backend = git_backend(&state);
+    let branch = backend.current_branch(&final_path).await.unwrap_or("main".to_string());
 
-    Git::fetch_tags(&final_path).await;
-    let tags = Git::get_filtered_tags(&final_path, 10).await.unwrap_or_default();
-    let display_names: Vec<String> = tags.iter().map(|(_, d)| d.clone()).collect();
-    let original_tags: Vec<String> = tags.iter().map(|(o, _)| o.clone()).collect();
-    let current_version = display_names.first().cloned().unwrap_or_default();
+    backend.fetch_tags(&final_path, &options).await.ok();
+    let rules = state.lock().unwrap().channel_rules.clone();
+    let tags = backend.get_filtered_tags(&final_path, &rules, TagSortMode::default(), VERSION_LIST_LIMIT).await.unwrap_or_default();
+    let display_names: Vec<String> = tags.iter().map(|t| t.display_name.clone()).collect();
+    let original_tags: Vec<String> = tags.iter().map(|t| t.original_tag.clone()).collect();
+    let version_details: Vec<Option<TagDetails>> = tags.iter().map(|t| t.details.clone()).collect();
+    let head = backend.head(&final_path, &original_tags).await.ok();
+    let status = read_status(backend.as_ref(), &final_path, head.as_ref(), &[], None).await.ok();
+    let has_warning = Git::submodules_out_of_sync(&final_path).await;
 
-    CloneProgressEvent {
-        repo_name: project_name.clone(),
-        progress: 90,
-        message: "Saving repository info...".to_string(),
-        received_bytes: None,
-        total_objects: None,
-        received_objects: None,
-        speed: None,
-    }.emit(&app).ok();
+    emit_clone_stage(&app, &project_name, 90, "Saving repository info...");
 
     let new_repo = {
         let mut state = state.lock().unwrap();
         let id_max = state.local_repositories.iter().map(|repo| repo.id).max().unwrap_or(0);
         
-        let repo = RepositoryInfo {
+        let mut repo = RepositoryInfo {
             id: id_max + 1,
             name: project_name.clone(),
             remote_url,
             branch,
             path: final_path,
-            game_version: current_version,
+            game_version: "".to_string(),
             game_versions: display_names,
             server: "".to_string(),
             server_options: original_tags,
-            has_warning: false,
+            has_warning,
             last_sync_time: Some(chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string()),
+            clone_options: options,
+            channel_rules: None,
+            tag_sort: TagSortMode::default(),
+            version_details,
+            clean_policy: CleanPolicy::default(),
+            head: None,
+            switched_at: Some(chrono::Local::now().to_rfc3339()),
+            status,
+            auto_refresh: AutoRefresh::default(),
+            follow: None,
+            follow_log: vec![],
+            pin: None,
         };
-        
+        show_head(&mut repo, head, &rules);
+        record_history(&mut state, SwitchSource::Clone, None, &repo);
+
         state.local_repositories.push(repo.clone());
         repo
     };
 
-    save_state(app.clone(), state).ok();
+    save_state(app.clone(), state).ok();
+
+    emit_clone_stage(&app, &project_name, 100, "Clone complete!");
+
+    CloneCompleteEvent {
+        repo_name: project_name,
+        success: true,
+        cancelled: false,
+        error_message: None,
+    }.emit(&app).ok();
+
+    Ok(new_repo)
+}
+
+/// Lists clones that were interrupted by an app exit: pending records that are no longer
+/// running, plus any `.tmp_<name>` directory left under the root path without a record.
+#[tauri::command]
+#[specta::specta]
+pub async fn get_interrupted_clones(state: State<'_, Mutex<AppState>>) -> Result<Vec<InterruptedClone>, String> {
+    let (root_path, pending) = {
+        let state = state.lock().unwrap();
+        let pending: Vec<PendingClone> = state.pending_clones
+            .iter()
+            .filter(|p| !state.clone_jobs.contains_key(&p.name))
+            .cloned()
+            .collect();
+        (state.path_root.clone(), pending)
+    };
+
+    let mut interrupted: Vec<InterruptedClone> = pending
+        .into_iter()
+        .map(|p| InterruptedClone {
+            has_partial_data: Path::new(&p.temp_path).exists(),
+            name: p.name,
+            remote_url: Some(p.remote_url),
+            temp_path: p.temp_path,
+            started_at: Some(p.started_at),
+        })
+        .collect();
+
+    if root_path.is_empty() || !Path::new(&root_path).exists() {
+        return Ok(interrupted);
+    }
+
+    let entries = std::fs::read_dir(&root_path)
+        .map_err(|e| format!("Failed to read root path: {}", e))?;
+
+    let running: Vec<String> = {
+        let state = state.lock().unwrap();
+        state.clone_jobs.keys().cloned().collect()
+    };
+
+    for entry in entries.flatten() {
+        if !entry.path().is_dir() {
+            continue;
+        }
+        let dir_name = entry.file_name().to_string_lossy().to_string();
+        let Some(name) = dir_name.strip_prefix(".tmp_") else {
+            continue;
+        };
+        if name.is_empty() || running.iter().any(|r| r == name) || interrupted.iter().any(|i| i.name == name) {
+            continue;
+        }
+
+        let temp_path = format!("{}/{}", root_path, dir_name);
+        let remote_url = Git::remote_url(&temp_path).await.ok().filter(|u| !u.is_empty());
+        interrupted.push(InterruptedClone {
+            name: name.to_string(),
+            remote_url,
+            temp_path,
+            started_at: None,
+            has_partial_data: true,
+        });
+    }
+
+    Ok(interrupted)
+}
+
+/// Continues an interrupted clone by fetching into the partial repository left in
+/// `.tmp_<name>`, then finishes it exactly like a fresh clone.
+#[tauri::command]
+#[specta::specta]
+pub async fn resume_clone(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    repo_name: String,
+) -> Result<RepositoryInfo, CommandError> {
+    let interrupted = get_interrupted_clones(state.clone()).await?
+        .into_iter()
+        .find(|i| i.name == repo_name)
+        .ok_or(format!("No interrupted clone found for '{}'", repo_name))?;
+
+    let remote_url = interrupted.remote_url
+        .ok_or("Remote URL of the interrupted clone is unknown")?;
+
+    let root_path = {
+        let state = state.lock().unwrap();
+        state.path_root.clone()
+    };
+    let final_path = format!("{}/{}", root_path, repo_name);
+    if Path::new(&final_path).exists() {
+        return Err(format!("Directory '{}' already exists", repo_name).into());
+    }
+
+    // Checked before anything is started, so the partial repository stays as it is.
+    let backend = git_backend(&state);
+    if !backend.supports_resume() {
+        return Err(GitError::Unsupported { operation: "resuming an interrupted clone".to_string() }.into());
+    }
+
+    // Directories found without a record fall back to a plain full clone.
+    let options = {
+        let state = state.lock().unwrap();
+        state.pending_clones
+            .iter()
+            .find(|p| p.name == repo_name)
+            .map(|p| p.options.clone())
+            .unwrap_or_default()
+    };
+
+    let job = PendingClone {
+        name: repo_name.clone(),
+        remote_url,
+        temp_path: interrupted.temp_path,
+        started_at: interrupted.started_at
+            .unwrap_or_else(|| chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string()),
+        options,
+    };
+    let cancel_rx = begin_clone_job(&app, &state, job.clone())?;
+
+    println!("[DEBUG] Resuming clone: {} -> {}", job.remote_url, job.temp_path);
+
+    emit_clone_stage(&app, &repo_name, 0, "Resuming...");
+
+    let (tx, progress_task) = spawn_progress_forwarder(&app, &repo_name);
+    let clone_result = backend.resume_clone(&job.temp_path, &job.remote_url, &job.options, tx, cancel_rx).await;
+    println!("[DEBUG] resume_clone on the {:?} backend returned: {:?}", backend.kind(), clone_result);
+
+    progress_task.abort();
+
+    finish_clone(app, state, job, final_path, clone_result, true).await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn discard_interrupted_clone(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    repo_name: String,
+) -> Result<bool, String> {
+    let interrupted = get_interrupted_clones(state.clone()).await?
+        .into_iter()
+        .find(|i| i.name == repo_name)
+        .ok_or(format!("No interrupted clone found for '{}'", repo_name))?;
+
+    if Path::new(&interrupted.temp_path).exists() {
+        std::fs::remove_dir_all(&interrupted.temp_path)
+            .map_err(|e| format!("Failed to delete temporary directory: {}", e))?;
+    }
+
+    {
+        let mut st = state.lock().unwrap();
+        st.pending_clones.retain(|p| p.name != repo_name);
+    }
+
+    save_state(app, state).ok();
+    Ok(true)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn cancel_clone(state: State<'_, Mutex<AppState>>, repo_name: String) -> Result<bool, String> {
+    let state = state.lock().unwrap();
+    let job = state.clone_jobs.get(&repo_name)
+        .ok_or(format!("No clone in progress for '{}'", repo_name))?;
+
+    // clone_repository owns the cleanup: it kills git, removes the temp
+    // directory and emits the cancelled CloneCompleteEvent.
+    job.cancel_tx.send(true)
+        .map_err(|_| "Clone has already finished".to_string())?;
+
+    Ok(true)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn add_project(state: State<'_, Mutex<AppState>>, remote_url: String, project_name: String) -> Vec<RepositoryInfo> {
+    let mut state = state.lock().unwrap();
+
+    let id_max = state.local_repositories.iter().map(|repo| repo.id).max().unwrap_or(0);
+    let already_exists = state.local_repositories.iter().any(|repo| repo.remote_url == remote_url);
+    if already_exists {
+        return state.local_repositories.clone();
+    }
+    if project_name.trim().is_empty() {
+        return state.local_repositories.clone();
+    }
+
+    state.local_repositories.push(RepositoryInfo {
+        id: id_max + 1,
+        name: project_name,
+        remote_url,
+        branch: "main".to_string(),
+        game_version: "".to_string(),
+        game_versions: vec![],
+        path: "".to_string(),
+        server: "".to_string(),
+        server_options: vec![],
+        has_warning: false,
+        last_sync_time: None,
+        clone_options: CloneOptions::default(),
+        channel_rules: None,
+        tag_sort: TagSortMode::default(),
+        version_details: vec![],
+        clean_policy: CleanPolicy::default(),
+        head: None,
+        switched_at: None,
+        status: None,
+        auto_refresh: AutoRefresh::default(),
+        follow: None,
+        follow_log: vec![],
+        pin: None,
+    });
+
+    state.local_repositories.clone()
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_filtered_tags(state: State<'_, Mutex<AppState>>, repo_path: String) -> Result<Vec<TagInfo>, CommandError> {
+    if !Path::new(&repo_path).exists() {
+        return Err("Repository path does not exist".into());
+    }
+
+    let (rules, sort) = {
+        let state = state.lock().unwrap();
+        let repo = state.local_repositories.iter().find(|r| r.path == repo_path);
+        (channel_rules_for(&state, repo), repo.map(|r| r.tag_sort).unwrap_or_default())
+    };
+
+    let tags = git_backend(&state).get_filtered_tags(&repo_path, &rules, sort, VERSION_LIST_LIMIT).await?;
+    Ok(tags)
+}
+
+/// Searches every tag of a repository, a page at a time.
+#[tauri::command]
+#[specta::specta]
+pub async fn query_tags(state: State<'_, Mutex<AppState>>, repo_id: u32, query: TagQuery) -> Result<TagPage, CommandError> {
+    let (repo_path, rules, sort) = tag_listing(&state, repo_id)?;
+    let page = git_backend(&state).query_tags(&repo_path, &rules, sort, &query).await?;
+    Ok(page)
+}
+
+/// Finds the tag for a typed version like `1.4.7`, so it can be switched to directly.
+#[tauri::command]
+#[specta::specta]
+pub async fn resolve_version(state: State<'_, Mutex<AppState>>, repo_id: u32, version: String) -> Result<TagInfo, CommandError> {
+    let (repo_path, rules, sort) = tag_listing(&state, repo_id)?;
+    git_backend(&state)
+        .find_version(&repo_path, &rules, sort, &version)
+        .await?
+        .ok_or_else(|| format!("No tag found for version {}", version).into())
+}
+
+/// Commits between two version list entries (tags, `BRANCH:` entries or commit
+/// hashes), for showing what a switch would change.
+#[tauri::command]
+#[specta::specta]
+pub async fn get_changelog(state: State<'_, Mutex<AppState>>, repo_id: u32, from: String, to: String) -> Result<Changelog, CommandError> {
+    let (repo_path, _, _) = tag_listing(&state, repo_id)?;
+    let (from, to) = (channels::revision(&from), channels::revision(&to));
+
+    let (added, added_truncated) = Git::log_range(&repo_path, &from, &to, CHANGELOG_LIMIT).await?;
+    let (removed, removed_truncated) = Git::log_range(&repo_path, &to, &from, CHANGELOG_LIMIT).await?;
+    Ok(Changelog { added, removed, truncated: added_truncated || removed_truncated })
+}
+
+/// Path, channel rules and tag order of a repository that exists on disk.
+fn tag_listing(state: &State<'_, Mutex<AppState>>, repo_id: u32) -> Result<(String, Vec<ChannelRule>, TagSortMode), CommandError> {
+    let state = state.lock().unwrap();
+    let repo = state.local_repositories
+        .iter()
+        .find(|r| r.id == repo_id)
+        .ok_or("Repository not found")?;
+    if !Path::new(&repo.path).exists() {
+        return Err("Repository path does not exist".into());
+    }
+    Ok((repo.path.clone(), channel_rules_for(&state, Some(repo)), repo.tag_sort))
+}
+
+/// The repository's own channel rules, or the global ones when it has none.
+fn channel_rules_for(state: &AppState, repo: Option<&RepositoryInfo>) -> Vec<ChannelRule> {
+    repo.and_then(|r| r.channel_rules.clone())
+        .unwrap_or_else(|| state.channel_rules.clone())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn refresh_repository(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    repo_id: u32,
+) -> Result<RepositoryInfo, CommandError> {
+    let _guard = RepoGuard::claim(&app, repo_id).ok_or(REPOSITORY_BUSY)?;
+    refresh_versions(&app, &state, repo_id).await
+}
+
+/// Fetches tags and rebuilds a repository's version list and status, then lets
+/// follow mode act on it. The caller holds the repository's `RepoGuard`.
+async fn refresh_versions(
+    app: &AppHandle,
+    state: &State<'_, Mutex<AppState>>,
+    repo_id: u32,
+) -> Result<RepositoryInfo, CommandError> {
+    let (previous, rules) = {
+        let state = state.lock().unwrap();
+        let repo = state.local_repositories.iter().find(|r| r.id == repo_id).cloned();
+        let rules = channel_rules_for(&state, repo.as_ref());
+        (repo, rules)
+    };
+
+    let previous = previous.ok_or("Repository not found")?;
+    let repo_path = previous.path.clone();
+
+    if !Path::new(&repo_path).exists() {
+        return Err("Repository path does not exist".into());
+    }
+
+    let backend = git_backend(state);
+    backend
+        .fetch_tags(&repo_path, &previous.clone_options)
+        .await
+        .map_err(|e| with_credential_request(app, &previous.name, &previous.remote_url, e))?;
+
+    let tags = backend.get_filtered_tags(&repo_path, &rules, previous.tag_sort, VERSION_LIST_LIMIT).await?;
+    let display_names: Vec<String> = tags.iter().map(|t| t.display_name.clone()).collect();
+    let original_tags: Vec<String> = tags.iter().map(|t| t.original_tag.clone()).collect();
+    let version_details: Vec<Option<TagDetails>> = tags.iter().map(|t| t.details.clone()).collect();
+    let head = backend.head(&repo_path, &original_tags).await.ok();
+    let mut status = read_status(backend.as_ref(), &repo_path, head.as_ref(), &tags, previous.switched_at.as_deref()).await.ok();
+    if let (Some(pin), Some(status)) = (&previous.pin, status.as_mut()) {
+        status.pin_drift = pin_drift(&repo_path, pin, &previous.clone_options).await;
+    }
+    let sync_time = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let has_warning = Git::submodules_out_of_sync(&repo_path).await;
+
+    let result = {
+        let mut state_guard = state.lock().unwrap();
+        if let Some(repo) = state_guard.local_repositories.iter_mut().find(|r| r.id == repo_id) {
+            repo.game_versions = display_names;
+            repo.server_options = original_tags;
+            repo.version_details = version_details;
+            show_head(repo, head, &rules);
+            repo.last_sync_time = Some(sync_time);
+            repo.has_warning = has_warning;
+            repo.status = status;
+            Some(repo.clone())
+        } else {
+            None
+        }
+    };
+
+    if let Some(repo) = result {
+        save_state(app.clone(), state.clone()).ok();
+        for tag in new_versions(&previous, &tags, &rules) {
+            NewVersionAvailableEvent {
+                repo_id,
+                repo_name: repo.name.clone(),
+                channel: tag.channel.clone(),
+                tag: tag.original_tag.clone(),
+                display_name: tag.display_name.clone(),
+            }.emit(app).ok();
+        }
+        return Ok(follow_channel(app, state, repo, &tags).await);
+    }
+
+    Err("Repository not found".into())
+}
+
+/// Refreshes every cloned repository, at most `concurrency` at a time (the saved
+/// `RefreshSettings::concurrency` when `None`), and returns the list afterwards.
+/// Each result is also emitted as a `RepositoryRefreshedEvent` as soon as it is in.
+#[tauri::command]
+#[specta::specta]
+pub async fn refresh_all(app: AppHandle, state: State<'_, Mutex<AppState>>, concurrency: Option<u32>) -> Result<Vec<RepositoryInfo>, String> {
+    let (repo_ids, permits) = {
+        let mut state = state.lock().unwrap();
+        let permits = match concurrency {
+            Some(0) => return Err("Concurrency must be at least 1".to_string()),
+            Some(n) => Arc::new(Semaphore::new(n as usize)),
+            None => refresh_permits(&mut state),
+        };
+        (cloned_repository_ids(&state), permits)
+    };
+    refresh_repositories(app, repo_ids, permits).await;
+    Ok(state.lock().unwrap().local_repositories.clone())
+}
+
+/// The launch refresh, run once per app start when `RefreshSettings::on_launch` is
+/// set. The page calls it after attaching its event listeners, so no
+/// `RepositoryRefreshedEvent` goes out before anyone listens. Returns the list afterwards.
+#[tauri::command]
+#[specta::specta]
+pub async fn refresh_on_launch(app: AppHandle, state: State<'_, Mutex<AppState>>) -> Result<Vec<RepositoryInfo>, String> {
+    let launch = {
+        let mut state = state.lock().unwrap();
+        let due = state.refresh_settings.on_launch && !state.launch_refreshed;
+        state.launch_refreshed = true;
+        due.then(|| (cloned_repository_ids(&state), refresh_permits(&mut state)))
+    };
+    if let Some((repo_ids, permits)) = launch {
+        refresh_repositories(app, repo_ids, permits).await;
+    }
+    Ok(state.lock().unwrap().local_repositories.clone())
+}
+
+/// Repositories with a checkout to refresh.
+fn cloned_repository_ids(state: &AppState) -> Vec<u32> {
+    state.local_repositories
+        .iter()
+        .filter(|repo| !repo.path.is_empty())
+        .map(|repo| repo.id)
+        .collect()
+}
+
+/// The permits shared by `refresh_all` and the scheduler, so together they never
+/// fetch more than `RefreshSettings::concurrency` repositories at once.
+pub fn refresh_permits(state: &mut AppState) -> Arc<Semaphore> {
+    let concurrency = state.refresh_settings.concurrency.max(1);
+    match &state.refresh_permits {
+        Some((size, permits)) if *size == concurrency => permits.clone(),
+        _ => {
+            let permits = Arc::new(Semaphore::new(concurrency as usize));
+            state.refresh_permits = Some((concurrency, permits.clone()));
+            permits
+        }
+    }
+}
+
+/// Refreshes `repo_ids` as `permits` allow, emitting a `RepositoryRefreshedEvent` as
+/// each finishes. A repository that is already being refreshed or switched is skipped.
+pub async fn refresh_repositories(app: AppHandle, repo_ids: Vec<u32>, permits: Arc<Semaphore>) {
+    let mut jobs = JoinSet::new();
+    for repo_id in repo_ids {
+        let app = app.clone();
+        let permits = permits.clone();
+        jobs.spawn(async move {
+            let _permit = permits.acquire_owned().await;
+            let Some(_guard) = RepoGuard::claim(&app, repo_id) else {
+                println!("[refresh] Skipping repository {}: {}", repo_id, REPOSITORY_BUSY);
+                return;
+            };
+            let repo_name = {
+                let state = app.state::<Mutex<AppState>>();
+                let state = state.lock().unwrap();
+                state.local_repositories.iter().find(|r| r.id == repo_id).map(|r| r.name.clone()).unwrap_or_default()
+            };
+            let (repository, error) = match refresh_versions(&app, &app.state(), repo_id).await {
+                Ok(repo) => (Some(repo), None),
+                Err(e) => {
+                    println!("[refresh] Refreshing {} failed: {}", repo_name, e);
+                    (None, Some(e))
+                }
+            };
+            RepositoryRefreshedEvent { repo_id, repo_name, repository, error }.emit(&app).ok();
+        });
+    }
+    while jobs.join_next().await.is_some() {}
+}
+
+/// Marks a repository as having a refresh or version switch running, so a second
+/// one from the scheduler, `refresh_all` or the user is turned away instead of
+/// racing it for `index.lock`. The mark goes when the guard is dropped.
+struct RepoGuard {
+    app: AppHandle,
+    repo_id: u32,
+}
+
+impl RepoGuard {
+    fn claim(app: &AppHandle, repo_id: u32) -> Option<RepoGuard> {
+        let state = app.state::<Mutex<AppState>>();
+        let claimed = state.lock().unwrap().busy_repositories.insert(repo_id);
+        claimed.then(|| RepoGuard { app: app.clone(), repo_id })
+    }
+}
 
-    CloneProgressEvent {
-        repo_name: project_name.clone(),
-        progress: 100,
-        message: "Clone complete!".to_string(),
-        received_bytes: None,
-        total_objects: None,
-        received_objects: None,
-        speed: None,
-    }.emit(&app).ok();
+impl Drop for RepoGuard {
+    fn drop(&mut self) {
+        let state = self.app.state::<Mutex<AppState>>();
+        state.lock().unwrap().busy_repositories.remove(&self.repo_id);
+    }
+}
 
-    CloneCompleteEvent {
-        repo_name: project_name,
-        success: true,
-        error_message: None,
-    }.emit(&app).ok();
+/// Entries of a refreshed version list that `previous` did not list, on the
+/// channels it watches. A repository without a list yet has nothing to compare.
+fn new_versions<'a>(previous: &RepositoryInfo, tags: &'a [TagInfo], rules: &[ChannelRule]) -> Vec<&'a TagInfo> {
+    if previous.server_options.is_empty() {
+        return Vec::new();
+    }
+    let watched = match &previous.auto_refresh.channels {
+        channels if channels.is_empty() => previous.head.as_ref().and_then(|h| head_channel(h, rules)).into_iter().collect(),
+        channels => channels.clone(),
+    };
+    tags.iter()
+        .filter(|t| !previous.server_options.contains(&t.original_tag) && watched.contains(&t.channel))
+        .collect()
+}
 
-    Ok(new_repo)
+/// The channel the checked-out tag or branch belongs to under `rules`.
+fn head_channel(head: &HeadInfo, rules: &[ChannelRule]) -> Option<String> {
+    let resolver = ChannelResolver::new(rules);
+    match &head.reference {
+        HeadRef::Tag { name } => resolver.resolve_tag(name).map(|t| t.channel),
+        HeadRef::Branch { name, .. } => resolver.resolve_branches(std::slice::from_ref(name)).into_iter().next().map(|t| t.channel),
+        HeadRef::Detached { .. } => None,
+    }
 }
 
+/// Extends the history of a shallow clone by `depth` commits, or unshallows it
+/// completely when `depth` is `None`. The stored clone options follow along.
 #[tauri::command]
 #[specta::specta]
-pub fn add_project(state: State<'_, Mutex<AppState>>, remote_url: String, project_name: String) -> Vec<RepositoryInfo> {
-    let mut state = state.lock().unwrap();
+pub async fn deepen_repository(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    repo_id: u32,
+    depth: Option<u32>,
+) -> Result<RepositoryInfo, CommandError> {
+    let repo = {
+        let state = state.lock().unwrap();
+        state.local_repositories
+            .iter()
+            .find(|r| r.id == repo_id)
+            .cloned()
+    };
 
-    let id_max = state.local_repositories.iter().map(|repo| repo.id).max().unwrap_or(0);
-    let already_exists = state.local_repositories.iter().any(|repo| repo.remote_url == remote_url);
-    if already_exists {
-        return state.local_repositories.clone();
-    }
-    if project_name.trim().is_empty() {
-        return state.local_repositories.clone();
+    let repo = repo.ok_or("Repository not found")?;
+
+    if !Path::new(&repo.path).exists() {
+        return Err("Repository path does not exist".into());
     }
 
-    state.local_repositories.push(RepositoryInfo {
-        id: id_max + 1,
-        name: project_name,
-        remote_url,
-        branch: "main".to_string(),
-        game_version: "".to_string(),
-        game_versions: vec![],
-        path: "".to_string(),
-        server: "".to_string(),
-        server_options: vec![],
-        has_warning: false,
-        last_sync_time: None,
-    });
+    let _guard = RepoGuard::claim(&app, repo_id).ok_or(REPOSITORY_BUSY)?;
+    Git::deepen(&repo.path, depth, &repo.clone_options)
+        .await
+        .map_err(|e| with_credential_request(&app, &repo.name, &repo.remote_url, e))?;
 
-    state.local_repositories.clone()
+    let result = {
+        let mut state_guard = state.lock().unwrap();
+        state_guard.local_repositories.iter_mut().find(|r| r.id == repo_id).map(|repo| {
+            repo.clone_options.depth = match (repo.clone_options.depth, depth) {
+                (Some(current), Some(extra)) => Some(current + extra),
+                _ => None,
+            };
+            repo.clone()
+        })
+    };
+
+    let repo = result.ok_or("Repository not found")?;
+    save_state(app, state).ok();
+    Ok(repo)
 }
 
+/// Replaces the sparse-checkout profile of a repository and applies it right away.
+/// An empty list turns sparse-checkout off and restores the full tree.
 #[tauri::command]
 #[specta::specta]
-pub async fn get_filtered_tags(repo_path: String) -> Result<Vec<TagInfo>, String> {
-    if !Path::new(&repo_path).exists() {
-        return Err("Repository path does not exist".to_string());
-    }
-
-    match Git::get_filtered_tags(&repo_path, 10).await {
-        Some(tags) => {
-            let tag_infos: Vec<TagInfo> = tags
-                .into_iter()
-                .map(|(original, display)| TagInfo {
-                    original_tag: original,
-                    display_name: display,
-                })
-                .collect();
-            Ok(tag_infos)
+pub async fn set_sparse_paths(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    repo_id: u32,
+    paths: Vec<String>,
+) -> Result<RepositoryInfo, CommandError> {
+    let mut normalized: Vec<String> = Vec::new();
+    for path in paths {
+        let path = path.trim().replace('\\', "/").trim_matches('/').to_string();
+        if path.is_empty() || normalized.contains(&path) {
+            continue;
         }
-        None => Err("Failed to get tags".to_string()),
+        normalized.push(path);
     }
+
+    let _guard = RepoGuard::claim(&app, repo_id).ok_or(REPOSITORY_BUSY)?;
+    sparse_checkout(&app, &state, repo_id, Some(normalized)).await
 }
 
+/// Re-applies the saved sparse-checkout profile, e.g. after the tree was changed outside the app.
 #[tauri::command]
 #[specta::specta]
-pub async fn refresh_repository(
+pub async fn apply_sparse_checkout(
     app: AppHandle,
     state: State<'_, Mutex<AppState>>,
     repo_id: u32,
-) -> Result<RepositoryInfo, String> {
-    let repo_path = {
+) -> Result<RepositoryInfo, CommandError> {
+    let _guard = RepoGuard::claim(&app, repo_id).ok_or(REPOSITORY_BUSY)?;
+    sparse_checkout(&app, &state, repo_id, None).await
+}
+
+/// Applies `paths`, or the saved profile when `None`, and stores them once git took
+/// them, so the next switch never re-applies a set that did not reach the tree.
+/// The caller holds the repository's `RepoGuard`.
+async fn sparse_checkout(
+    app: &AppHandle,
+    state: &State<'_, Mutex<AppState>>,
+    repo_id: u32,
+    paths: Option<Vec<String>>,
+) -> Result<RepositoryInfo, CommandError> {
+    let repo = {
         let state = state.lock().unwrap();
         state.local_repositories
             .iter()
             .find(|r| r.id == repo_id)
-            .map(|r| r.path.clone())
+            .cloned()
     };
 
-    let repo_path = repo_path.ok_or("Repository not found")?;
+    let repo = repo.ok_or("Repository not found")?;
 
-    if !Path::new(&repo_path).exists() {
-        return Err("Repository path does not exist".to_string());
+    if !Path::new(&repo.path).exists() {
+        return Err("Repository path does not exist".into());
     }
 
-    Git::fetch_tags(&repo_path).await;
-
-    let tags = Git::get_filtered_tags(&repo_path, 10).await.unwrap_or_default();
-    let display_names: Vec<String> = tags.iter().map(|(_, d)| d.clone()).collect();
-    let original_tags: Vec<String> = tags.iter().map(|(o, _)| o.clone()).collect();
-    
-    let current_version = display_names.first().cloned().unwrap_or_default();
-    let sync_time = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let paths = paths.unwrap_or(repo.clone_options.sparse_paths);
+    Git::sparse_checkout_set(&repo.path, &paths).await?;
 
-    let result = {
-        let mut state_guard = state.lock().unwrap();
-        if let Some(repo) = state_guard.local_repositories.iter_mut().find(|r| r.id == repo_id) {
-            repo.game_versions = display_names;
-            repo.server_options = original_tags;
-            repo.game_version = current_version;
-            repo.last_sync_time = Some(sync_time);
-            Some(repo.clone())
-        } else {
-            None
-        }
+    let repo = {
+        let mut state = state.lock().unwrap();
+        state.local_repositories.iter_mut().find(|r| r.id == repo_id).map(|repo| {
+            repo.clone_options.sparse_paths = paths;
+            repo.clone()
+        })
     };
+    let repo = repo.ok_or("Repository not found")?;
+    save_state(app.clone(), state.clone()).ok();
 
-    if let Some(repo) = result {
-        save_state(app, state).ok();
-        return Ok(repo);
+    sync_lfs_content(app, &repo.name, &repo.path, &repo.clone_options).await?;
+
+    Ok(repo)
+}
+
+/// Sets or clears the SSH private key used for the repository's remote. It is stored
+/// with the clone options, so fetches, switches and submodule updates all use it.
+#[tauri::command]
+#[specta::specta]
+pub fn set_ssh_key(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    repo_id: u32,
+    key_path: Option<String>,
+) -> Result<RepositoryInfo, String> {
+    let key_path = key_path.map(|p| p.trim().to_string()).filter(|p| !p.is_empty());
+    if let Some(path) = &key_path {
+        if !Path::new(path).is_file() {
+            return Err(format!("SSH key '{}' does not exist", path));
+        }
     }
 
-    Err("Repository not found".to_string())
+    let repo = {
+        let mut state = state.lock().unwrap();
+        let repo = state.local_repositories
+            .iter_mut()
+            .find(|r| r.id == repo_id)
+            .ok_or("Repository not found")?;
+        repo.clone_options.ssh_key_path = key_path;
+        repo.clone()
+    };
+    save_state(app, state).ok();
+    Ok(repo)
 }
 
 #[tauri::command]
@@ -364,6 +1363,7 @@ pub async fn delete_repository(
     {
         let mut st = state.lock().unwrap();
         st.local_repositories.retain(|r| r.id != repo_id);
+        st.version_history.remove(&repo_id);
     }
     
     save_state(app, state).ok();
@@ -381,6 +1381,11 @@ pub fn save_state(app: AppHandle, state: State<'_, Mutex<AppState>>) -> Result<b
     
     store.set("path_root", serde_json::json!(&state.path_root));
     store.set("local_repositories", serde_json::json!(&state.local_repositories));
+    store.set("pending_clones", serde_json::json!(&state.pending_clones));
+    store.set("git_backend", serde_json::json!(state.git_backend.kind()));
+    store.set("channel_rules", serde_json::json!(&state.channel_rules));
+    store.set("version_history", serde_json::json!(&state.version_history));
+    store.set("refresh_settings", serde_json::json!(&state.refresh_settings));
     
     store.save()
         .map_err(|e| format!("Failed to save store: {}", e))?;
@@ -407,53 +1412,498 @@ pub fn load_state(app: AppHandle, state: State<'_, Mutex<AppState>>) -> Result<b
             state.local_repositories = repositories;
         }
     }
+
+    if let Some(pending) = store.get("pending_clones") {
+        if let Ok(pending_clones) = serde_json::from_value::<Vec<PendingClone>>(pending.clone()) {
+            state.pending_clones = pending_clones;
+        }
+    }
+
+    if let Some(kind) = store.get("git_backend") {
+        // A backend this build lacks (gix without the feature) falls back to the CLI.
+        if let Some(backend) = serde_json::from_value::<GitBackendKind>(kind.clone()).ok().and_then(|k| backend::backend_for(k).ok()) {
+            state.git_backend = backend;
+        }
+    }
+
+    if let Some(rules) = store.get("channel_rules") {
+        if let Ok(channel_rules) = serde_json::from_value::<Vec<ChannelRule>>(rules.clone()) {
+            state.channel_rules = channel_rules;
+        }
+    }
+
+    if let Some(history) = store.get("version_history") {
+        if let Ok(version_history) = serde_json::from_value::<HashMap<u32, Vec<HistoryEntry>>>(history.clone()) {
+            state.version_history = version_history;
+        }
+    }
+
+    if let Some(settings) = store.get("refresh_settings") {
+        if let Ok(refresh_settings) = serde_json::from_value::<RefreshSettings>(settings.clone()) {
+            state.refresh_settings = refresh_settings;
+        }
+    }
     
     Ok(true)
 }
 
+/// Lists what `change_version` would discard in a repository.
+#[tauri::command]
+#[specta::specta]
+pub async fn preflight_switch(state: State<'_, Mutex<AppState>>, repo_id: u32) -> Result<SwitchPreflight, CommandError> {
+    let (repo_path, sparse, clean_policy) = {
+        let state = state.lock().unwrap();
+        let repo = state.local_repositories
+            .iter()
+            .find(|r| r.id == repo_id)
+            .ok_or("Repository not found")?;
+        (repo.path.clone(), !repo.clone_options.sparse_paths.is_empty(), repo.clean_policy.clone())
+    };
+
+    if !Path::new(&repo_path).exists() {
+        return Err("Repository path does not exist".into());
+    }
+
+    Ok(preflight(git_backend(&state).as_ref(), &repo_path, sparse, &clean_policy).await?)
+}
+
+/// The work behind `preflight_switch`, also run by follow mode before it switches.
+async fn preflight(backend: &dyn GitBackend, repo_path: &str, sparse: bool, clean_policy: &CleanPolicy) -> Result<SwitchPreflight, GitError> {
+    let (mut modified, mut staged, _) = backend.working_tree_changes(repo_path).await?;
+    // Untracked files only go if the clean policy removes them, so list what it would.
+    let (mut untracked, mut ignored) = backend.clean_preview(repo_path, clean_policy).await?;
+    let mut ignored_tracked = backend.hidden_tracked_files(repo_path, sparse).await?;
+    let (local_commits, commits_truncated) = backend.local_commits(repo_path, PREFLIGHT_LIST_LIMIT).await?;
+
+    let mut truncated = commits_truncated;
+    for list in [&mut modified, &mut staged, &mut untracked, &mut ignored, &mut ignored_tracked] {
+        truncated |= list.len() > PREFLIGHT_LIST_LIMIT;
+        list.truncate(PREFLIGHT_LIST_LIMIT);
+    }
+
+    Ok(SwitchPreflight { modified, staged, untracked, ignored, ignored_tracked, local_commits, truncated })
+}
+
+/// True when a switch would lose none of what `preflight` lists.
+fn discards_nothing(preflight: &SwitchPreflight) -> bool {
+    preflight.modified.is_empty()
+        && preflight.staged.is_empty()
+        && preflight.untracked.is_empty()
+        && preflight.ignored.is_empty()
+        && preflight.ignored_tracked.is_empty()
+        && preflight.local_commits.is_empty()
+}
+
 #[tauri::command]
 #[specta::specta]
 pub async fn change_version(
+    app: AppHandle,
     state: State<'_, Mutex<AppState>>,
     repo_id: u32,
     tag: String,
-) -> Result<RepositoryInfo, String> {
-    let repo_path = {
+    mode: SwitchMode,
+    override_pin: bool,
+) -> Result<RepositoryInfo, CommandError> {
+    check_pin(&state, repo_id, &tag, override_pin)?;
+    let _guard = RepoGuard::claim(&app, repo_id).ok_or(REPOSITORY_BUSY)?;
+    switch_version(&app, &state, repo_id, &tag, None, mode, SwitchSource::Manual).await
+}
+
+/// Returns a repository to what it had checked out before its latest clone or switch.
+#[tauri::command]
+#[specta::specta]
+pub async fn rollback_version(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    repo_id: u32,
+    mode: SwitchMode,
+    override_pin: bool,
+) -> Result<RepositoryInfo, CommandError> {
+    let previous = {
+        let state = state.lock().unwrap();
+        state.version_history.get(&repo_id).and_then(|h| h.last()).and_then(|entry| entry.from.clone())
+    };
+    let previous = previous.ok_or("There is no earlier version to roll back to")?;
+    let (tag, commit) = rollback_target(&previous)?;
+
+    check_pin(&state, repo_id, &tag, override_pin)?;
+    let _guard = RepoGuard::claim(&app, repo_id).ok_or(REPOSITORY_BUSY)?;
+    switch_version(&app, &state, repo_id, &tag, commit.as_deref(), mode, SwitchSource::Rollback).await
+}
+
+/// What `switch_version` is given to return to `point`. A branch has moved on since,
+/// so its recorded commit comes along for the switch to reset the branch to.
+fn rollback_target(point: &HistoryPoint) -> Result<(String, Option<String>), CommandError> {
+    match &point.reference {
+        HeadRef::Tag { name } => Ok((name.clone(), None)),
+        HeadRef::Branch { name, .. } => Ok((format!("{}{}", channels::BRANCH_PREFIX, name), Some(point.commit.clone()))),
+        HeadRef::Detached { describe } => {
+            Err(format!("The previous checkout, {}, was not on a tag or branch", describe).into())
+        }
+    }
+}
+
+/// Clones and switches of a repository, newest first.
+#[tauri::command]
+#[specta::specta]
+pub fn get_version_history(state: State<'_, Mutex<AppState>>, repo_id: u32) -> Vec<HistoryEntry> {
+    let state = state.lock().unwrap();
+    state.version_history.get(&repo_id).map(|h| h.iter().rev().cloned().collect()).unwrap_or_default()
+}
+
+/// Refuses a switch away from a pinned version unless `override_pin` is set.
+fn check_pin(state: &State<'_, Mutex<AppState>>, repo_id: u32, tag: &str, override_pin: bool) -> Result<(), CommandError> {
+    if override_pin {
+        return Ok(());
+    }
+    let state = state.lock().unwrap();
+    let pin = state.local_repositories.iter().find(|r| r.id == repo_id).and_then(|r| r.pin.as_ref());
+    match pin {
+        Some(pin) if pin.tag != tag => Err(pinned_message(pin).into()),
+        _ => Ok(()),
+    }
+}
+
+/// Adds a clone or switch that ended at `repo`'s current head to its history.
+fn record_history(state: &mut AppState, source: SwitchSource, from: Option<HistoryPoint>, repo: &RepositoryInfo) {
+    let Some(head) = &repo.head else {
+        return;
+    };
+    let history = state.version_history.entry(repo.id).or_default();
+    history.push(HistoryEntry {
+        at: chrono::Local::now().to_rfc3339(),
+        source,
+        from,
+        to: HistoryPoint {
+            reference: head.reference.clone(),
+            commit: head.commit.clone(),
+            display_name: repo.game_version.clone(),
+        },
+    });
+    let excess = history.len().saturating_sub(HISTORY_LIMIT);
+    history.drain(..excess);
+}
+
+fn pinned_message(pin: &Pin) -> String {
+    format!("Pinned to {} by {}: {}", pin.tag, pin.pinned_by, pin.reason)
+}
+
+/// How the pinned ref differs on origin, or `None` when it is unchanged or origin
+/// could not be reached.
+async fn pin_drift(repo_path: &str, pin: &Pin, options: &CloneOptions) -> Option<PinDrift> {
+    let refname = match pin.tag.strip_prefix(channels::BRANCH_PREFIX) {
+        Some(branch) => format!("refs/heads/{}", branch),
+        None => format!("refs/tags/{}", pin.tag),
+    };
+    match Git::remote_commit(repo_path, &refname, options).await {
+        Ok(None) => Some(PinDrift::Missing),
+        Ok(Some(commit)) if commit != pin.commit => Some(PinDrift::Moved { commit }),
+        Ok(Some(_)) => None,
+        Err(e) => {
+            println!("[refresh_repository] Could not check pinned {}: {}", refname, e);
+            None
+        }
+    }
+}
+
+/// Checks out `tag` or a `BRANCH:` entry, then resets it to `commit` when given;
+/// shared by `change_version`, `rollback_version` and follow mode.
+/// The caller holds the repository's `RepoGuard`.
+async fn switch_version(
+    app: &AppHandle,
+    state: &State<'_, Mutex<AppState>>,
+    repo_id: u32,
+    tag: &str,
+    commit: Option<&str>,
+    mode: SwitchMode,
+    source: SwitchSource,
+) -> Result<RepositoryInfo, CommandError> {
+    let repo = {
         let state = state.lock().unwrap();
         state.local_repositories
             .iter()
             .find(|r| r.id == repo_id)
-            .map(|r| r.path.clone())
+            .map(|r| {
+                let from = r.head.as_ref().map(|head| HistoryPoint {
+                    reference: head.reference.clone(),
+                    commit: head.commit.clone(),
+                    display_name: r.game_version.clone(),
+                });
+                (r.name.clone(), r.remote_url.clone(), r.path.clone(), r.clone_options.clone(), r.clean_policy.clone(), channel_rules_for(&state, Some(r)), from)
+            })
     };
 
-    let repo_path = repo_path.ok_or("Repository not found")?;
+    let (repo_name, remote_url, repo_path, options, clean_policy, rules, from) = repo.ok_or("Repository not found")?;
 
     if !Path::new(&repo_path).exists() {
-        return Err("Repository path does not exist".to_string());
+        return Err("Repository path does not exist".into());
+    }
+
+    if Git::uses_lfs(&repo_path) && !Git::is_lfs_installed().await {
+        return Err(GitError::LfsMissing.into());
+    }
+
+    if mode == SwitchMode::Backup && has_local_work(&repo_path).await? {
+        let backup = Git::create_backup(&repo_path, &format!("Before switching to {}", tag)).await?;
+        println!("[change_version] Backed up local changes of {} to {}", repo_name, backup);
     }
 
-    let success = if tag.starts_with("BRANCH:") {
-        let branch_name = tag.trim_start_matches("BRANCH:");
-        Git::checkout_remote_branch(&repo_path, branch_name, true).await
+    let backend = git_backend(state);
+    if let Some(branch_name) = tag.strip_prefix(channels::BRANCH_PREFIX) {
+        backend
+            .switch_to_branch(&repo_path, branch_name, true, &options)
+            .await
+            .map_err(|e| with_credential_request(app, &repo_name, &remote_url, e))?;
     } else {
-        Git::checkout_tag(&repo_path, &tag, true).await
+        backend.switch_to_tag(&repo_path, tag, true).await?;
+    }
+    if let Some(commit) = commit {
+        backend.reset_to(&repo_path, commit).await?;
+    }
+
+    if !options.sparse_paths.is_empty() {
+        Git::sparse_checkout_reapply(&repo_path).await?;
+    }
+    // After the checkout, so files the target version tracks are never counted as leftovers.
+    Git::clean(&repo_path, &clean_policy).await?;
+
+    sync_submodules(app, &repo_name, &repo_path, &options)
+        .await
+        .map_err(|e| with_credential_request(app, &repo_name, &remote_url, e))?;
+    sync_lfs_content(app, &repo_name, &repo_path, &options)
+        .await
+        .map_err(|e| with_credential_request(app, &repo_name, &remote_url, e))?;
+    let has_warning = Git::submodules_out_of_sync(&repo_path).await;
+    // The switched-to tag goes first so it is the one shown when others share its commit.
+    let preferred = [tag.to_string()];
+    let head = backend.head(&repo_path, &preferred).await?;
+    let status = read_status(backend.as_ref(), &repo_path, Some(&head), &[], None).await.ok();
+
+    let result = {
+        let mut state_guard = state.lock().unwrap();
+        let repo = state_guard.local_repositories.iter_mut().find(|r| r.id == repo_id).map(|repo| {
+            repo.has_warning = has_warning;
+            repo.switched_at = Some(chrono::Local::now().to_rfc3339());
+            repo.status = status;
+            show_head(repo, Some(head), &rules);
+            repo.clone()
+        });
+        if let Some(repo) = &repo {
+            record_history(&mut state_guard, source, from, repo);
+        }
+        repo
     };
-    
-    if !success {
-        return Err("Failed to checkout".to_string());
+
+    match result {
+        Some(repo) => {
+            save_state(app.clone(), state.clone()).ok();
+            Ok(repo)
+        }
+        None => Err("Repository not found".into()),
     }
+}
 
-    let mut state = state.lock().unwrap();
-    if let Some(repo) = state.local_repositories.iter_mut().find(|r| r.id == repo_id) {
-        if let Some(idx) = repo.server_options.iter().position(|t| t == &tag) {
-            if let Some(display) = repo.game_versions.get(idx) {
-                repo.game_version = display.clone();
-            }
+/// Moves a repository in follow mode to the newest entry of its channel in `tags`,
+/// unless it is already there. Local work is never discarded: the switch is skipped
+/// and the skip recorded instead. Runs under the `RepoGuard` of the refresh that
+/// called it, so it cannot race a user's switch. Returns the repository as it is afterwards.
+async fn follow_channel(
+    app: &AppHandle,
+    state: &State<'_, Mutex<AppState>>,
+    repo: RepositoryInfo,
+    tags: &[TagInfo],
+) -> RepositoryInfo {
+    let Some(follow) = &repo.follow else {
+        return repo;
+    };
+    let Some(target) = tags.iter().find(|t| {
+        t.channel == follow.channel && !(follow.tags_only && t.original_tag.starts_with(channels::BRANCH_PREFIX))
+    }) else {
+        return repo;
+    };
+
+    let up_to_date = repo.head.as_ref().is_some_and(|head| match (&head.reference, target.original_tag.strip_prefix(channels::BRANCH_PREFIX)) {
+        (HeadRef::Branch { name, .. }, Some(branch)) => {
+            name == branch && repo.status.as_ref().and_then(|s| s.behind).unwrap_or(0) == 0
+        }
+        (_, Some(_)) | (HeadRef::Branch { .. }, None) => false,
+        // Compared by commit, since another tag on the same commit may be the one shown.
+        (_, None) => target.details.as_ref().is_some_and(|d| d.commit == head.commit),
+    });
+    if up_to_date {
+        return repo;
+    }
+
+    let skipped = match &repo.pin {
+        Some(pin) => Some(pinned_message(pin)),
+        // Checked up front, so a switch is either skipped whole or made, never
+        // abandoned halfway on a conflict.
+        None => match is_dirty(git_backend(state).as_ref(), &repo).await {
+            Ok(false) => None,
+            Ok(true) => Some("The checkout has local changes, untracked files or commits a switch would discard".to_string()),
+            Err(e) => Some(e.to_string()),
+        },
+    };
+    let already_skipped = repo.follow_log.last().is_some_and(|last| last.tag == target.original_tag && last.skipped.is_some());
+    if skipped.is_some() && already_skipped {
+        return repo;
+    }
+
+    let (switched, skipped) = match skipped {
+        Some(reason) => (None, Some(reason)),
+        None => match switch_version(app, state, repo.id, &target.original_tag, None, SwitchMode::Discard, SwitchSource::Automatic).await {
+            Ok(switched) => (Some(switched), None),
+            Err(e) => (None, Some(e.to_string())),
+        },
+    };
+    if let Some(reason) = &skipped {
+        println!("[follow] Not switching {} to {}: {}", repo.name, target.original_tag, reason);
+    }
+
+    let record = FollowRecord {
+        at: chrono::Local::now().to_rfc3339(),
+        from: repo.game_version.clone(),
+        to: target.display_name.clone(),
+        tag: target.original_tag.clone(),
+        skipped,
+    };
+    let updated = {
+        let mut state_guard = state.lock().unwrap();
+        state_guard.local_repositories.iter_mut().find(|r| r.id == repo.id).map(|r| {
+            r.follow_log.push(record.clone());
+            let excess = r.follow_log.len().saturating_sub(FOLLOW_LOG_LIMIT);
+            r.follow_log.drain(..excess);
+            r.clone()
+        })
+    };
+    let Some(updated) = updated else {
+        return switched.unwrap_or(repo);
+    };
+    save_state(app.clone(), state.clone()).ok();
+    AutoSwitchEvent {
+        repo_id: updated.id,
+        repo_name: updated.name.clone(),
+        record,
+        repository: updated.clone(),
+    }.emit(app).ok();
+    updated
+}
+
+/// Whether follow mode has to leave a checkout alone: a switch would discard
+/// something the preflight lists, or there are untracked files at all. Those count
+/// whatever the clean policy, since nobody is there to say they can stay or go.
+async fn is_dirty(backend: &dyn GitBackend, repo: &RepositoryInfo) -> Result<bool, GitError> {
+    let (_, _, untracked) = backend.working_tree_changes(&repo.path).await?;
+    if !untracked.is_empty() {
+        return Ok(true);
+    }
+    let preflight = preflight(backend, &repo.path, !repo.clone_options.sparse_paths.is_empty(), &repo.clean_policy).await?;
+    Ok(!discards_nothing(&preflight))
+}
+
+/// Reads the working tree and, on a tracking branch, how far it is from its
+/// upstream. `new_tags` lists the `tags` dated after `since`, the last clone or switch.
+async fn read_status(
+    backend: &dyn GitBackend,
+    repo_path: &str,
+    head: Option<&HeadInfo>,
+    tags: &[TagInfo],
+    since: Option<&str>,
+) -> Result<RepositoryStatus, GitError> {
+    let (modified, staged, untracked) = backend.working_tree_changes(repo_path).await?;
+    let ahead_behind = match head.map(|h| &h.reference) {
+        Some(HeadRef::Branch { upstream: Some(upstream), .. }) => Some(backend.ahead_behind(repo_path, upstream).await?),
+        _ => None,
+    };
+
+    let parse = |date: &str| chrono::DateTime::parse_from_rfc3339(date).ok();
+    let new_tags = match since.and_then(parse) {
+        Some(since) => tags
+            .iter()
+            .filter(|t| {
+                t.details
+                    .as_ref()
+                    .and_then(|d| d.tag_date.as_deref().or(d.commit_date.as_deref()))
+                    .and_then(parse)
+                    .is_some_and(|date| date > since)
+            })
+            .map(|t| t.display_name.clone())
+            .collect(),
+        None => Vec::new(),
+    };
+
+    Ok(RepositoryStatus {
+        modified: modified.len() as u32,
+        staged: staged.len() as u32,
+        untracked: untracked.len() as u32,
+        ahead: ahead_behind.map(|(ahead, _)| ahead),
+        behind: ahead_behind.map(|(_, behind)| behind),
+        new_tags,
+        pin_drift: None,
+    })
+}
+
+/// Records `head` and points `game_version` at its entry in the version list.
+/// A tag that is not listed yet, e.g. one picked from a tag search, is appended.
+/// Without a readable HEAD, the newest listed version is assumed.
+fn show_head(repo: &mut RepositoryInfo, head: Option<HeadInfo>, rules: &[ChannelRule]) {
+    let display = |repo: &RepositoryInfo, entry: &str| {
+        repo.server_options.iter().position(|t| t == entry).and_then(|idx| repo.game_versions.get(idx).cloned())
+    };
+
+    repo.game_version = match head.as_ref().map(|h| &h.reference) {
+        None => repo.game_versions.first().cloned().unwrap_or_default(),
+        Some(HeadRef::Branch { name, .. }) => {
+            display(repo, &format!("{}{}", channels::BRANCH_PREFIX, name)).unwrap_or_else(|| name.clone())
         }
-        return Ok(repo.clone());
+        Some(HeadRef::Tag { name }) => display(repo, name).unwrap_or_else(|| {
+            let entry = ChannelResolver::new(rules).resolve_tag(name).unwrap_or_else(|| channels::unmatched(name));
+            repo.game_versions.push(entry.display_name.clone());
+            repo.server_options.push(entry.original_tag);
+            repo.version_details.push(entry.details);
+            entry.display_name
+        }),
+        Some(HeadRef::Detached { describe }) => describe.clone(),
+    };
+    repo.head = head;
+}
+
+/// Whether a switch would lose anything: changed or untracked files, or commits
+/// that exist only in this checkout.
+async fn has_local_work(repo_path: &str) -> Result<bool, GitError> {
+    let (modified, staged, untracked) = Git::working_tree_changes(repo_path).await?;
+    if !(modified.is_empty() && staged.is_empty() && untracked.is_empty()) {
+        return Ok(true);
     }
+    let (local_commits, _) = Git::local_commits(repo_path, 1).await?;
+    Ok(!local_commits.is_empty())
+}
+
+/// Backups taken by `change_version` in backup mode, newest first.
+#[tauri::command]
+#[specta::specta]
+pub async fn list_backups(state: State<'_, Mutex<AppState>>, repo_id: u32) -> Result<Vec<BackupInfo>, CommandError> {
+    let (repo_path, _, _) = tag_listing(&state, repo_id)?;
+    Ok(Git::list_backups(&repo_path).await?)
+}
+
+/// Re-applies a backup's changes on top of the current checkout.
+#[tauri::command]
+#[specta::specta]
+pub async fn restore_backup(app: AppHandle, state: State<'_, Mutex<AppState>>, repo_id: u32, name: String) -> Result<bool, CommandError> {
+    let (repo_path, _, _) = tag_listing(&state, repo_id)?;
+    let _guard = RepoGuard::claim(&app, repo_id).ok_or(REPOSITORY_BUSY)?;
+    Git::restore_backup(&repo_path, &name).await?;
+    Ok(true)
+}
 
-    Err("Repository not found".to_string())
+#[tauri::command]
+#[specta::specta]
+pub async fn delete_backup(app: AppHandle, state: State<'_, Mutex<AppState>>, repo_id: u32, name: String) -> Result<Vec<BackupInfo>, CommandError> {
+    let (repo_path, _, _) = tag_listing(&state, repo_id)?;
+    let _guard = RepoGuard::claim(&app, repo_id).ok_or(REPOSITORY_BUSY)?;
+    Git::delete_backup(&repo_path, &name).await?;
+    Ok(Git::list_backups(&repo_path).await?)
 }
 
 #[tauri::command]
@@ -470,6 +1920,7 @@ pub async fn remove_from_list(
             return Err("Repository not found".to_string());
         }
         st.local_repositories.retain(|r| r.id != repo_id);
+        st.version_history.remove(&repo_id);
     }
     
     save_state(app, state).ok();
@@ -483,8 +1934,43 @@ pub fn reset_app_data(app: AppHandle, state: State<'_, Mutex<AppState>>) -> Resu
         let mut st = state.lock().unwrap();
         st.path_root = "".to_string();
         st.local_repositories = vec![];
+        st.pending_clones = vec![];
+        st.git_backend = Arc::new(backend::CliBackend);
+        st.channel_rules = channels::default_rules();
+        st.version_history.clear();
+        st.refresh_settings = RefreshSettings::default();
     }
+    credentials::clear().ok();
     
     save_state(app, state).ok();
     Ok(true)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(reference: HeadRef) -> HistoryPoint {
+        HistoryPoint { reference, commit: "4b825dc642cb6eb9a060e54bf8d69288fbee4904".to_string(), display_name: String::new() }
+    }
+
+    #[test]
+    fn rollback_to_branch_resets_to_recorded_commit() {
+        let branch = point(HeadRef::Branch { name: "main".to_string(), upstream: Some("origin/main".to_string()) });
+        let (tag, commit) = rollback_target(&branch).unwrap();
+        assert_eq!(tag, "BRANCH:main");
+        assert_eq!(commit.as_deref(), Some("4b825dc642cb6eb9a060e54bf8d69288fbee4904"));
+    }
+
+    #[test]
+    fn rollback_to_tag_checks_out_tag() {
+        let (tag, commit) = rollback_target(&point(HeadRef::Tag { name: "dev-1.4.19".to_string() })).unwrap();
+        assert_eq!(tag, "dev-1.4.19");
+        assert_eq!(commit, None);
+    }
+
+    #[test]
+    fn rollback_to_detached_head_is_refused() {
+        assert!(rollback_target(&point(HeadRef::Detached { describe: "4b825dc".to_string() })).is_err());
+    }
+}
@@ -1,22 +1,53 @@
 mod command;
+mod scheduler;
 
-use std::sync::Mutex;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
 use specta_typescript::Typescript;
 use tauri::{Manager, State};
+use tokio::sync::{watch, Semaphore};
 use tauri_specta::{collect_commands, collect_events};
-use modules::types::{AppInitializeEvent, CloneProgressEvent, CloneCompleteEvent};
-use crate::modules::types::RepositoryInfo;
-use crate::command::{get_root_path, set_root_path, add_project, clone_repository, validate_repo_name, get_repositories, get_filtered_tags, refresh_repository, change_version, delete_repository, save_state, load_state, remove_from_list, reset_app_data};
+use modules::types::{AppInitializeEvent, CloneProgressEvent, CloneCompleteEvent, CredentialRequestEvent, NewVersionAvailableEvent, AutoSwitchEvent, RepositoryRefreshedEvent};
+use crate::modules::types::{RepositoryInfo, PendingClone, ChannelRule, HistoryEntry, RefreshSettings};
+use crate::modules::backend::{GitBackend, CliBackend};
+use crate::command::{get_root_path, set_root_path, get_git_backend, set_git_backend, get_available_git_backends, get_refresh_settings, set_refresh_settings, get_credentials, save_credential, delete_credential, get_channel_rules, set_channel_rules, set_repository_channel_rules, set_tag_sort, set_clean_policy, set_auto_refresh, set_follow_channel, pin_repository, unpin_repository, add_project, clone_repository, validate_repo_name, cancel_clone, get_interrupted_clones, resume_clone, discard_interrupted_clone, get_repositories, get_filtered_tags, query_tags, resolve_version, get_changelog, refresh_repository, refresh_all, refresh_on_launch, deepen_repository, set_sparse_paths, apply_sparse_checkout, set_ssh_key, preflight_switch, change_version, rollback_version, get_version_history, list_backups, restore_backup, delete_backup, delete_repository, save_state, load_state, remove_from_list, reset_app_data};
 
 pub mod modules {
+    pub mod backend;
+    pub mod channels;
+    pub mod credentials;
+    pub mod error;
     pub mod git;
+    pub mod git_command;
+    #[cfg(feature = "gix-backend")]
+    pub mod gix_backend;
     pub mod types;
+    pub mod version;
 }
 
 pub struct AppState {
     count: u32,
     pub path_root: String,
-    pub local_repositories: Vec<RepositoryInfo>
+    pub local_repositories: Vec<RepositoryInfo>,
+    pub pending_clones: Vec<PendingClone>,
+    pub clone_jobs: HashMap<String, CloneJob>,
+    pub git_backend: Arc<dyn GitBackend>,
+    /// Global channel rules; a repository's own `channel_rules` take precedence.
+    pub channel_rules: Vec<ChannelRule>,
+    /// Clones and switches per repository id, oldest first.
+    pub version_history: HashMap<u32, Vec<HistoryEntry>>,
+    pub refresh_settings: RefreshSettings,
+    /// Shared by bulk and scheduled refreshes, with the concurrency it was made for.
+    pub refresh_permits: Option<(u32, Arc<Semaphore>)>,
+    /// Repositories a refresh or version switch is running on.
+    pub busy_repositories: HashSet<u32>,
+    /// Set once `refresh_on_launch` has run, so reloading the page does not repeat it.
+    pub launch_refreshed: bool,
+}
+
+/// A running `git clone`, keyed by repository name in `AppState::clone_jobs`.
+pub struct CloneJob {
+    pub cancel_tx: watch::Sender<bool>,
 }
 
 #[tauri::command]
@@ -34,20 +65,56 @@ pub fn run() {
             increase_counter,
             get_root_path,
             set_root_path,
+            get_git_backend,
+            set_git_backend,
+            get_available_git_backends,
+            get_refresh_settings,
+            set_refresh_settings,
+            get_credentials,
+            save_credential,
+            delete_credential,
+            get_channel_rules,
+            set_channel_rules,
+            set_repository_channel_rules,
+            set_tag_sort,
+            set_clean_policy,
+            set_auto_refresh,
+            set_follow_channel,
+            pin_repository,
+            unpin_repository,
             add_project,
             clone_repository,
             validate_repo_name,
+            cancel_clone,
+            get_interrupted_clones,
+            resume_clone,
+            discard_interrupted_clone,
             get_repositories,
             get_filtered_tags,
+            query_tags,
+            resolve_version,
+            get_changelog,
             refresh_repository,
+            refresh_all,
+            refresh_on_launch,
+            deepen_repository,
+            set_sparse_paths,
+            apply_sparse_checkout,
+            set_ssh_key,
+            preflight_switch,
             change_version,
+            rollback_version,
+            get_version_history,
+            list_backups,
+            restore_backup,
+            delete_backup,
             delete_repository,
             save_state,
             load_state,
             remove_from_list,
             reset_app_data
         ])
-        .events(collect_events![AppInitializeEvent, CloneProgressEvent, CloneCompleteEvent]);
+        .events(collect_events![AppInitializeEvent, CloneProgressEvent, CloneCompleteEvent, CredentialRequestEvent, NewVersionAvailableEvent, AutoSwitchEvent, RepositoryRefreshedEvent]);
 
     builder
         .export(Typescript::default(), "../src/lib/bindings.ts")
@@ -62,14 +129,27 @@ pub fn run() {
         .manage(Mutex::new(AppState {
             count: 0,
             path_root: "".to_string(),
-            local_repositories: vec![]
+            local_repositories: vec![],
+            pending_clones: vec![],
+            clone_jobs: HashMap::new(),
+            git_backend: Arc::new(CliBackend),
+            channel_rules: modules::channels::default_rules(),
+            version_history: HashMap::new(),
+            refresh_settings: RefreshSettings::default(),
+            refresh_permits: None,
+            busy_repositories: HashSet::new(),
+            launch_refreshed: false,
         }))
         .setup(move |app| {
             builder.mount_events(app);
+
+            let app_data_dir = app.path().app_data_dir()?;
+            modules::credentials::init(&app_data_dir);
             
             let handle = app.handle().clone();
             let state = app.state::<Mutex<AppState>>();
-            load_state(handle, state).ok();
+            load_state(handle.clone(), state).ok();
+            scheduler::start(handle);
             Ok(())
         })
         .invoke_handler(invoke_handler)
@@ -2,5 +2,9 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    // git starts this binary again as its askpass helper; answer and exit without a window.
+    if let Some(code) = lib::modules::credentials::run_askpass() {
+        std::process::exit(code);
+    }
     lib::run();
 }
@@ -0,0 +1,531 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+use tokio::sync::{mpsc, watch};
+use crate::modules::channels::{self, ChannelResolver};
+use crate::modules::error::GitError;
+use crate::modules::git::{CloneProgress, Git};
+use crate::modules::types::{ChangelogCommit, ChannelRule, CleanPolicy, CloneOptions, GitBackendKind, HeadInfo, TagInfo, TagPage, TagQuery, TagRef, TagSortMode};
+use crate::modules::version::Version;
+
+#[cfg(feature = "gix-backend")]
+use crate::modules::gix_backend::GixBackend;
+
+/// The git operations the commands rely on, implemented once on top of the git
+/// executable and once with gix for machines where git is not installed.
+#[async_trait]
+pub trait GitBackend: Send + Sync {
+    fn kind(&self) -> GitBackendKind;
+
+    /// Clones `remote_url` into `target_dir`, forwarding progress until done or
+    /// until `cancel_rx` flips to true.
+    async fn clone_with_progress(
+        &self,
+        target_dir: &str,
+        remote_url: &str,
+        options: &CloneOptions,
+        progress_tx: mpsc::Sender<CloneProgress>,
+        cancel_rx: watch::Receiver<bool>,
+    ) -> Result<(), GitError>;
+
+    /// Whether `resume_clone` can continue a partial clone with this backend.
+    fn supports_resume(&self) -> bool {
+        true
+    }
+
+    /// Continues a clone interrupted in `target_dir`, keeping what was already
+    /// fetched, with progress and cancellation as in `clone_with_progress`.
+    async fn resume_clone(
+        &self,
+        target_dir: &str,
+        remote_url: &str,
+        options: &CloneOptions,
+        progress_tx: mpsc::Sender<CloneProgress>,
+        cancel_rx: watch::Receiver<bool>,
+    ) -> Result<(), GitError>;
+
+    /// Updates the remote-tracking branches from origin.
+    async fn fetch(&self, work_dir: &str, options: &CloneOptions) -> Result<(), GitError>;
+
+    /// Fetches every tag from origin, keeping a shallow clone at `options.depth`.
+    async fn fetch_tags(&self, work_dir: &str, options: &CloneOptions) -> Result<(), GitError>;
+
+    /// All tags with their details, newest first by `sort`'s date; `Version` lists
+    /// by creator date.
+    async fn list_tags(&self, work_dir: &str, sort: TagSortMode) -> Result<Vec<TagRef>, GitError>;
+
+    /// Remote-tracking branches, as `origin/<name>`.
+    async fn remote_branches(&self, work_dir: &str) -> Result<Vec<String>, GitError>;
+
+    /// Name of the checked-out branch, or `HEAD` when detached.
+    async fn current_branch(&self, work_dir: &str) -> Result<String, GitError>;
+
+    /// What HEAD is on. A checked-out branch wins over tags at the same commit;
+    /// among several tags, the first one in `preferred_tags` is picked.
+    async fn head(&self, work_dir: &str, preferred_tags: &[String]) -> Result<HeadInfo, GitError>;
+
+    /// Checks out the local `branch`, creating it from `origin/<branch>` if needed,
+    /// and brings it up to date with origin.
+    async fn checkout_branch(&self, work_dir: &str, branch: &str, options: &CloneOptions) -> Result<(), GitError>;
+
+    /// Checks out `tag` with a detached HEAD.
+    async fn checkout_tag(&self, work_dir: &str, tag: &str) -> Result<(), GitError>;
+
+    /// Discards all changes to tracked files.
+    async fn reset_hard(&self, work_dir: &str) -> Result<(), GitError>;
+
+    /// Moves HEAD, and the branch it is on if any, to `commit` and discards all
+    /// changes to tracked files.
+    async fn reset_to(&self, work_dir: &str, commit: &str) -> Result<(), GitError>;
+
+    /// Changed tracked files, as not staged and staged, and the untracked files that
+    /// are not ignored, with a wholly untracked directory listed once as `dir/`.
+    async fn working_tree_changes(&self, work_dir: &str) -> Result<(Vec<String>, Vec<String>, Vec<String>), GitError>;
+
+    /// How many commits HEAD has that `upstream` lacks, and the other way round.
+    async fn ahead_behind(&self, work_dir: &str, upstream: &str) -> Result<(u32, u32), GitError>;
+
+    /// Tracked files marked assume-unchanged, or skip-worktree unless `sparse`.
+    async fn hidden_tracked_files(&self, work_dir: &str, sparse: bool) -> Result<Vec<String>, GitError>;
+
+    /// Up to `limit` commits on HEAD that no remote branch or tag has, newest first,
+    /// and whether there were more.
+    async fn local_commits(&self, work_dir: &str, limit: usize) -> Result<(Vec<ChangelogCommit>, bool), GitError>;
+
+    /// What cleaning with `policy` would remove, as untracked and ignored paths.
+    async fn clean_preview(&self, work_dir: &str, policy: &CleanPolicy) -> Result<(Vec<String>, Vec<String>), GitError>;
+
+    /// Check if a specific remote branch exists (e.g., "dev", "qa")
+    async fn has_remote_branch(&self, work_dir: &str, branch_name: &str) -> Result<bool, GitError> {
+        let branches = self.remote_branches(work_dir).await?;
+        Ok(branches.iter().any(|b| {
+            let normalized = b.trim().replace("origin/", "");
+            normalized.eq_ignore_ascii_case(branch_name)
+        }))
+    }
+
+    /// Every version of a repository: branch entries first, then the tags a channel
+    /// rule matches (or all tags with `include_all`) in `sort` order, without
+    /// repeating a display name.
+    async fn list_versions(
+        &self,
+        work_dir: &str,
+        rules: &[ChannelRule],
+        sort: TagSortMode,
+        include_all: bool,
+    ) -> Result<Vec<TagInfo>, GitError> {
+        let all_tags = self.list_tags(work_dir, sort).await?;
+        let branches: Vec<String> = self
+            .remote_branches(work_dir)
+            .await?
+            .iter()
+            .filter(|b| !b.contains("->"))
+            .map(|b| b.trim().trim_start_matches("origin/").to_string())
+            .collect();
+
+        let resolver = ChannelResolver::new(rules);
+        let mut seen_versions = std::collections::HashSet::new();
+        let mut tags: Vec<TagInfo> = resolver
+            .resolve_branches(&branches)
+            .into_iter()
+            .filter(|entry| seen_versions.insert(entry.display_name.clone()))
+            .collect();
+
+        let mut tag_entries: Vec<TagInfo> = all_tags
+            .iter()
+            .filter_map(|tag| {
+                let entry = match resolver.resolve_tag(&tag.name) {
+                    None if include_all => Some(channels::unmatched(&tag.name)),
+                    entry => entry,
+                }?;
+                Some(TagInfo { details: Some(tag.details.clone()), ..entry })
+            })
+            .collect();
+        if sort == TagSortMode::Version {
+            // Stable, so equal versions and tags without one keep their date order.
+            tag_entries.sort_by_cached_key(|entry| std::cmp::Reverse(entry.version.as_deref().and_then(Version::find)));
+        }
+        tags.extend(tag_entries.into_iter().filter(|entry| seen_versions.insert(entry.display_name.clone())));
+
+        Ok(tags)
+    }
+
+    /// The short version list kept on a repository.
+    async fn get_filtered_tags(
+        &self,
+        work_dir: &str,
+        rules: &[ChannelRule],
+        sort: TagSortMode,
+        limit: usize,
+    ) -> Result<Vec<TagInfo>, GitError> {
+        let mut tags = self.list_versions(work_dir, rules, sort, false).await?;
+        tags.truncate(limit);
+        Ok(tags)
+    }
+
+    /// One page of the versions matching `query`.
+    async fn query_tags(
+        &self,
+        work_dir: &str,
+        rules: &[ChannelRule],
+        sort: TagSortMode,
+        query: &TagQuery,
+    ) -> Result<TagPage, GitError> {
+        let versions = self.list_versions(work_dir, rules, sort, query.include_all).await?;
+        let search = query.search.as_deref().map(str::trim).unwrap_or_default().to_lowercase();
+        let wanted = exact_version(&search);
+
+        let (mut exact, mut matching): (Vec<TagInfo>, Vec<TagInfo>) = (Vec::new(), Vec::new());
+        for entry in versions {
+            if query.channel.as_ref().is_some_and(|c| !c.eq_ignore_ascii_case(&entry.channel)) {
+                continue;
+            }
+            if wanted.is_some() && entry.version.as_deref().and_then(Version::find) == wanted {
+                exact.push(entry);
+            } else if entry.original_tag.to_lowercase().contains(&search) || entry.display_name.to_lowercase().contains(&search) {
+                matching.push(entry);
+            }
+        }
+        exact.extend(matching);
+
+        let total = exact.len();
+        let offset = (query.offset as usize).min(total);
+        let tags: Vec<TagInfo> = exact.into_iter().skip(offset).take(query.limit as usize).collect();
+        let has_more = offset + tags.len() < total;
+        Ok(TagPage { tags, total: total as u32, has_more })
+    }
+
+    /// The tag (or branch) for a typed version like `1.4.7` or `v1.4.7`, preferring
+    /// one a channel rule matches, however far down the list it is.
+    async fn find_version(
+        &self,
+        work_dir: &str,
+        rules: &[ChannelRule],
+        sort: TagSortMode,
+        version: &str,
+    ) -> Result<Option<TagInfo>, GitError> {
+        let Some(wanted) = exact_version(version.trim()) else {
+            return Ok(None);
+        };
+        let versions = self.list_versions(work_dir, rules, sort, true).await?;
+        let mut candidates = versions
+            .into_iter()
+            .filter(|entry| entry.version.as_deref().and_then(Version::find).as_ref() == Some(&wanted));
+        let first = candidates.next();
+        Ok(match first {
+            Some(entry) if entry.channel.is_empty() => Some(candidates.find(|e| !e.channel.is_empty()).unwrap_or(entry)),
+            entry => entry,
+        })
+    }
+
+    /// Checkout to a remote branch with fetch and pull
+    async fn switch_to_branch(&self, work_dir: &str, branch: &str, discard_all: bool, options: &CloneOptions) -> Result<(), GitError> {
+        if discard_all {
+            self.reset_hard(work_dir).await?;
+        }
+        self.fetch(work_dir, options).await?;
+        self.checkout_branch(work_dir, branch, options).await
+    }
+
+    async fn switch_to_tag(&self, work_dir: &str, tag: &str, discard_all: bool) -> Result<(), GitError> {
+        if discard_all {
+            self.reset_hard(work_dir).await?;
+        }
+        self.checkout_tag(work_dir, tag).await
+    }
+}
+
+/// `text` as a version when it is nothing but one, with an optional leading `v`.
+fn exact_version(text: &str) -> Option<Version> {
+    let text = text.strip_prefix(['v', 'V']).unwrap_or(text);
+    Version::find(text).filter(|v| v.to_string() == text)
+}
+
+/// Runs everything through the git executable in PATH.
+pub struct CliBackend;
+
+#[async_trait]
+impl GitBackend for CliBackend {
+    fn kind(&self) -> GitBackendKind {
+        GitBackendKind::Cli
+    }
+
+    async fn clone_with_progress(
+        &self,
+        target_dir: &str,
+        remote_url: &str,
+        options: &CloneOptions,
+        progress_tx: mpsc::Sender<CloneProgress>,
+        cancel_rx: watch::Receiver<bool>,
+    ) -> Result<(), GitError> {
+        Git::clone_with_progress(target_dir, remote_url, options, progress_tx, cancel_rx).await
+    }
+
+    async fn resume_clone(
+        &self,
+        target_dir: &str,
+        remote_url: &str,
+        options: &CloneOptions,
+        progress_tx: mpsc::Sender<CloneProgress>,
+        cancel_rx: watch::Receiver<bool>,
+    ) -> Result<(), GitError> {
+        Git::resume_clone(target_dir, remote_url, options, progress_tx, cancel_rx).await
+    }
+
+    async fn fetch(&self, work_dir: &str, options: &CloneOptions) -> Result<(), GitError> {
+        Git::fetch(work_dir, options).await
+    }
+
+    async fn fetch_tags(&self, work_dir: &str, options: &CloneOptions) -> Result<(), GitError> {
+        Git::fetch_tags_with(work_dir, options).await
+    }
+
+    async fn list_tags(&self, work_dir: &str, sort: TagSortMode) -> Result<Vec<TagRef>, GitError> {
+        Git::get_tags(work_dir, sort, usize::MAX).await
+    }
+
+    async fn remote_branches(&self, work_dir: &str) -> Result<Vec<String>, GitError> {
+        Git::remote_branch_list(work_dir).await
+    }
+
+    async fn current_branch(&self, work_dir: &str) -> Result<String, GitError> {
+        Git::current_branch(work_dir).await
+    }
+
+    async fn head(&self, work_dir: &str, preferred_tags: &[String]) -> Result<HeadInfo, GitError> {
+        Git::head(work_dir, preferred_tags).await
+    }
+
+    async fn checkout_branch(&self, work_dir: &str, branch: &str, options: &CloneOptions) -> Result<(), GitError> {
+        Git::checkout_tracking_branch(work_dir, branch, options).await
+    }
+
+    async fn checkout_tag(&self, work_dir: &str, tag: &str) -> Result<(), GitError> {
+        Git::checkout_tag(work_dir, tag).await
+    }
+
+    async fn reset_hard(&self, work_dir: &str) -> Result<(), GitError> {
+        Git::reset_hard(work_dir).await
+    }
+
+    async fn reset_to(&self, work_dir: &str, commit: &str) -> Result<(), GitError> {
+        Git::reset_to(work_dir, commit).await
+    }
+
+    async fn working_tree_changes(&self, work_dir: &str) -> Result<(Vec<String>, Vec<String>, Vec<String>), GitError> {
+        Git::working_tree_changes(work_dir).await
+    }
+
+    async fn ahead_behind(&self, work_dir: &str, upstream: &str) -> Result<(u32, u32), GitError> {
+        Git::ahead_behind(work_dir, upstream).await
+    }
+
+    async fn hidden_tracked_files(&self, work_dir: &str, sparse: bool) -> Result<Vec<String>, GitError> {
+        Git::hidden_tracked_files(work_dir, sparse).await
+    }
+
+    async fn local_commits(&self, work_dir: &str, limit: usize) -> Result<(Vec<ChangelogCommit>, bool), GitError> {
+        Git::local_commits(work_dir, limit).await
+    }
+
+    async fn clean_preview(&self, work_dir: &str, policy: &CleanPolicy) -> Result<(Vec<String>, Vec<String>), GitError> {
+        Git::clean_preview(work_dir, policy).await
+    }
+}
+
+/// Creates the backend selected in settings, failing for gix when this build does not include it.
+pub fn backend_for(kind: GitBackendKind) -> Result<Arc<dyn GitBackend>, String> {
+    match kind {
+        GitBackendKind::Cli => Ok(Arc::new(CliBackend)),
+        #[cfg(feature = "gix-backend")]
+        GitBackendKind::Gix => Ok(Arc::new(GixBackend)),
+        #[cfg(not(feature = "gix-backend"))]
+        GitBackendKind::Gix => Err("This build does not include the built-in git backend".to_string()),
+    }
+}
+
+/// Backends that can run on this machine: the CLI only when git is in PATH,
+/// gix whenever it was compiled in.
+pub async fn available_backends() -> Vec<GitBackendKind> {
+    let mut available = Vec::new();
+    if Git::is_git_in_path().await {
+        available.push(GitBackendKind::Cli);
+    }
+    if cfg!(feature = "gix-backend") {
+        available.push(GitBackendKind::Gix);
+    }
+    available
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use crate::modules::types::TagDetails;
+
+    /// A repository held in memory: tags newest first, whatever `sort` asks for,
+    /// and the writes made to it in order.
+    struct FakeBackend {
+        tags: Vec<&'static str>,
+        branches: Vec<&'static str>,
+        calls: Mutex<Vec<String>>,
+    }
+
+    impl FakeBackend {
+        fn new(tags: Vec<&'static str>) -> Self {
+            let branches = vec!["origin/HEAD -> origin/main", "origin/main", "origin/dev"];
+            FakeBackend { tags, branches, calls: Mutex::new(Vec::new()) }
+        }
+
+        fn record(&self, call: String) -> Result<(), GitError> {
+            self.calls.lock().unwrap().push(call);
+            Ok(())
+        }
+    }
+
+    fn unsupported<T>(operation: &str) -> Result<T, GitError> {
+        Err(GitError::Unsupported { operation: operation.to_string() })
+    }
+
+    #[async_trait]
+    impl GitBackend for FakeBackend {
+        fn kind(&self) -> GitBackendKind {
+            GitBackendKind::Cli
+        }
+
+        async fn clone_with_progress(
+            &self,
+            _target_dir: &str,
+            _remote_url: &str,
+            _options: &CloneOptions,
+            _progress_tx: mpsc::Sender<CloneProgress>,
+            _cancel_rx: watch::Receiver<bool>,
+        ) -> Result<(), GitError> {
+            unsupported("clone")
+        }
+
+        async fn resume_clone(
+            &self,
+            _target_dir: &str,
+            _remote_url: &str,
+            _options: &CloneOptions,
+            _progress_tx: mpsc::Sender<CloneProgress>,
+            _cancel_rx: watch::Receiver<bool>,
+        ) -> Result<(), GitError> {
+            unsupported("resume clone")
+        }
+
+        async fn fetch(&self, _work_dir: &str, _options: &CloneOptions) -> Result<(), GitError> {
+            self.record("fetch".to_string())
+        }
+
+        async fn fetch_tags(&self, _work_dir: &str, _options: &CloneOptions) -> Result<(), GitError> {
+            self.record("fetch_tags".to_string())
+        }
+
+        async fn list_tags(&self, _work_dir: &str, _sort: TagSortMode) -> Result<Vec<TagRef>, GitError> {
+            Ok(self
+                .tags
+                .iter()
+                .map(|name| TagRef {
+                    name: name.to_string(),
+                    details: TagDetails { commit: format!("commit of {}", name), commit_date: None, tag_date: None, tagger: None, message: None },
+                })
+                .collect())
+        }
+
+        async fn remote_branches(&self, _work_dir: &str) -> Result<Vec<String>, GitError> {
+            Ok(self.branches.iter().map(|b| b.to_string()).collect())
+        }
+
+        async fn current_branch(&self, _work_dir: &str) -> Result<String, GitError> {
+            Ok("HEAD".to_string())
+        }
+
+        async fn head(&self, _work_dir: &str, _preferred_tags: &[String]) -> Result<HeadInfo, GitError> {
+            unsupported("head")
+        }
+
+        async fn checkout_branch(&self, _work_dir: &str, branch: &str, _options: &CloneOptions) -> Result<(), GitError> {
+            self.record(format!("checkout_branch {}", branch))
+        }
+
+        async fn checkout_tag(&self, _work_dir: &str, tag: &str) -> Result<(), GitError> {
+            self.record(format!("checkout_tag {}", tag))
+        }
+
+        async fn reset_hard(&self, _work_dir: &str) -> Result<(), GitError> {
+            self.record("reset_hard".to_string())
+        }
+
+        async fn reset_to(&self, _work_dir: &str, commit: &str) -> Result<(), GitError> {
+            self.record(format!("reset_to {}", commit))
+        }
+
+        async fn working_tree_changes(&self, _work_dir: &str) -> Result<(Vec<String>, Vec<String>, Vec<String>), GitError> {
+            Ok((Vec::new(), Vec::new(), Vec::new()))
+        }
+
+        async fn ahead_behind(&self, _work_dir: &str, _upstream: &str) -> Result<(u32, u32), GitError> {
+            Ok((0, 0))
+        }
+
+        async fn hidden_tracked_files(&self, _work_dir: &str, _sparse: bool) -> Result<Vec<String>, GitError> {
+            Ok(Vec::new())
+        }
+
+        async fn local_commits(&self, _work_dir: &str, _limit: usize) -> Result<(Vec<ChangelogCommit>, bool), GitError> {
+            Ok((Vec::new(), false))
+        }
+
+        async fn clean_preview(&self, _work_dir: &str, _policy: &CleanPolicy) -> Result<(Vec<String>, Vec<String>), GitError> {
+            Ok((Vec::new(), Vec::new()))
+        }
+    }
+
+    fn original_tags(versions: &[TagInfo]) -> Vec<&str> {
+        versions.iter().map(|v| v.original_tag.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn lists_branches_then_matched_tags_without_repeats() {
+        let backend = FakeBackend::new(vec!["dev-1.4.20", "build-dev-1.4.20", "v1.0.0", "qa-1.4.19", "dev-1.4.19"]);
+        let rules = channels::default_rules();
+
+        let versions = backend.list_versions(".", &rules, TagSortMode::CreatorDate, false).await.unwrap();
+        assert_eq!(original_tags(&versions), ["BRANCH:dev", "dev-1.4.20", "qa-1.4.19", "dev-1.4.19"]);
+        assert_eq!(versions[0].display_name, "dev-latest");
+        assert_eq!(versions[1].details.as_ref().unwrap().commit, "commit of dev-1.4.20");
+
+        let all = backend.list_versions(".", &rules, TagSortMode::CreatorDate, true).await.unwrap();
+        assert_eq!(original_tags(&all), ["BRANCH:dev", "dev-1.4.20", "v1.0.0", "qa-1.4.19", "dev-1.4.19"]);
+        assert_eq!(all[2].channel, "");
+    }
+
+    #[tokio::test]
+    async fn version_sort_ignores_tag_dates() {
+        let backend = FakeBackend::new(vec!["dev-1.4.9", "dev-1.4.20", "dev-1.4.19"]);
+        let versions = backend.list_versions(".", &channels::default_rules(), TagSortMode::Version, false).await.unwrap();
+        assert_eq!(original_tags(&versions), ["BRANCH:dev", "dev-1.4.20", "dev-1.4.19", "dev-1.4.9"]);
+    }
+
+    #[tokio::test]
+    async fn finds_typed_version_preferring_a_channel() {
+        let backend = FakeBackend::new(vec!["1.4.19", "qa-1.4.19", "dev-1.4.20", "v2.0.0"]);
+        let rules = channels::default_rules();
+        let find = |version: &'static str| backend.find_version(".", &rules, TagSortMode::CreatorDate, version);
+
+        assert_eq!(find("1.4.19").await.unwrap().unwrap().original_tag, "qa-1.4.19");
+        assert_eq!(find("v1.4.20").await.unwrap().unwrap().original_tag, "dev-1.4.20");
+        assert_eq!(find("2.0.0").await.unwrap().unwrap().original_tag, "v2.0.0");
+        assert!(find("1.4").await.unwrap().is_none());
+        assert!(find("latest").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn switch_to_tag_resets_first_only_when_discarding() {
+        let backend = FakeBackend::new(Vec::new());
+        backend.switch_to_tag(".", "dev-1.4.20", true).await.unwrap();
+        backend.switch_to_tag(".", "qa-1.4.19", false).await.unwrap();
+        assert_eq!(*backend.calls.lock().unwrap(), ["reset_hard", "checkout_tag dev-1.4.20", "checkout_tag qa-1.4.19"]);
+    }
+}
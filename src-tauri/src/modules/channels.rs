@@ -0,0 +1,276 @@
+use regex::{Regex, RegexBuilder};
+use crate::modules::types::{ChannelMatcher, ChannelRule, TagInfo};
+use crate::modules::version::Version;
+
+/// Prefix of `TagInfo::original_tag` for entries that follow a branch instead of a tag.
+pub const BRANCH_PREFIX: &str = "BRANCH:";
+
+/// The git revision for a version list entry: `origin/<branch>` for branch entries,
+/// otherwise the tag name or commit hash as given.
+pub fn revision(entry: &str) -> String {
+    match entry.strip_prefix(BRANCH_PREFIX) {
+        Some(branch) => format!("origin/{}", branch),
+        None => entry.to_string(),
+    }
+}
+
+/// The rules used until the user configures their own: `dev`/`qa` branches as
+/// `-latest` entries, and tags mentioning dev or qa shown as `<channel>-<version>`.
+pub fn default_rules() -> Vec<ChannelRule> {
+    let branch = |name: &str| ChannelRule {
+        channel: name.to_string(),
+        matcher: ChannelMatcher::Branch { pattern: name.to_string() },
+        display_template: "{channel}-latest".to_string(),
+    };
+    let tag = |name: &str| ChannelRule {
+        channel: name.to_string(),
+        matcher: ChannelMatcher::Tag { regex: name.to_string() },
+        display_template: "{channel}-{version}".to_string(),
+    };
+    vec![branch("dev"), branch("qa"), tag("dev"), tag("qa")]
+}
+
+/// Checks every rule so a typo is reported when saving rather than silently ignored later.
+pub fn validate(rules: &[ChannelRule]) -> Result<(), String> {
+    for rule in rules {
+        if rule.channel.trim().is_empty() {
+            return Err("Every channel rule needs a channel name".to_string());
+        }
+        match &rule.matcher {
+            ChannelMatcher::Tag { regex } => {
+                tag_regex(regex).map_err(|e| format!("Invalid tag pattern for '{}': {}", rule.channel, e))?;
+            }
+            ChannelMatcher::Branch { pattern } if pattern.trim().is_empty() => {
+                return Err(format!("Branch pattern for '{}' is empty", rule.channel));
+            }
+            ChannelMatcher::Branch { .. } => {}
+        }
+    }
+    Ok(())
+}
+
+fn tag_regex(regex: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(regex).case_insensitive(true).build()
+}
+
+/// Channel rules with their tag patterns compiled once per listing.
+pub struct ChannelResolver<'a> {
+    rules: Vec<(&'a ChannelRule, Option<Regex>)>,
+}
+
+impl<'a> ChannelResolver<'a> {
+    /// Rules whose pattern does not compile are skipped.
+    pub fn new(rules: &'a [ChannelRule]) -> Self {
+        let rules = rules
+            .iter()
+            .filter_map(|rule| match &rule.matcher {
+                ChannelMatcher::Tag { regex } => match tag_regex(regex) {
+                    Ok(compiled) => Some((rule, Some(compiled))),
+                    Err(e) => {
+                        println!("[channels] Skipping rule '{}': {}", rule.channel, e);
+                        None
+                    }
+                },
+                ChannelMatcher::Branch { .. } => Some((rule, None)),
+            })
+            .collect();
+        ChannelResolver { rules }
+    }
+
+    /// The entry for `tag` from the first tag rule that matches it.
+    pub fn resolve_tag(&self, tag: &str) -> Option<TagInfo> {
+        self.rules.iter().find_map(|(rule, regex)| {
+            let captures = regex.as_ref()?.captures(tag)?;
+            // A `version` group in the pattern wins over the generic version guess.
+            let version = captures
+                .name("version")
+                .map(|m| m.as_str().to_string())
+                .or_else(|| Version::find(tag).map(|v| v.to_string()));
+            Some(TagInfo {
+                original_tag: tag.to_string(),
+                display_name: render(&rule.display_template, &rule.channel, tag, version.as_deref()),
+                channel: rule.channel.clone(),
+                version,
+                details: None,
+            })
+        })
+    }
+
+    /// Entries for the remote branches (`origin/` already stripped) matched by branch
+    /// rules, in rule order and then by name.
+    pub fn resolve_branches(&self, branches: &[String]) -> Vec<TagInfo> {
+        let mut entries = Vec::new();
+        for (rule, _) in &self.rules {
+            let ChannelMatcher::Branch { pattern } = &rule.matcher else {
+                continue;
+            };
+            let mut matched: Vec<&String> = branches.iter().filter(|b| glob_match(pattern, b)).collect();
+            matched.sort();
+            for branch in matched {
+                let version = Version::find(branch).map(|v| v.to_string());
+                entries.push(TagInfo {
+                    original_tag: format!("{}{}", BRANCH_PREFIX, branch),
+                    display_name: render(&rule.display_template, &rule.channel, branch, version.as_deref()),
+                    channel: rule.channel.clone(),
+                    version,
+                    details: None,
+                });
+            }
+        }
+        entries
+    }
+}
+
+/// The entry for a tag no rule matches, listed as-is under an empty channel.
+pub fn unmatched(tag: &str) -> TagInfo {
+    TagInfo {
+        original_tag: tag.to_string(),
+        display_name: tag.to_string(),
+        channel: String::new(),
+        version: Version::find(tag).map(|v| v.to_string()),
+        details: None,
+    }
+}
+
+/// Fills `{channel}`, `{name}` and `{version}`. Without a version the raw name is
+/// shown, since e.g. `dev-` would say nothing about which build it is.
+fn render(template: &str, channel: &str, name: &str, version: Option<&str>) -> String {
+    if template.contains("{version}") && version.is_none() {
+        return name.to_string();
+    }
+    template
+        .replace("{channel}", channel)
+        .replace("{name}", name)
+        .replace("{version}", version.unwrap_or_default())
+}
+
+/// Case-insensitive match of the whole name, where `*` stands for any run of
+/// characters, including `/`, and `?` for exactly one.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // The last `*` seen and where in the name its match currently ends, to retry
+    // with it taking one more character when the rest fails.
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    star = Some((star_p, star_n + 1));
+                    p = star_p + 1;
+                    n = star_n + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag_rule(channel: &str, regex: &str, display_template: &str) -> ChannelRule {
+        ChannelRule {
+            channel: channel.to_string(),
+            matcher: ChannelMatcher::Tag { regex: regex.to_string() },
+            display_template: display_template.to_string(),
+        }
+    }
+
+    fn branch_rule(channel: &str, pattern: &str) -> ChannelRule {
+        ChannelRule {
+            channel: channel.to_string(),
+            matcher: ChannelMatcher::Branch { pattern: pattern.to_string() },
+            display_template: "{channel}-{name}".to_string(),
+        }
+    }
+
+    #[test]
+    fn glob_matches_whole_name() {
+        let cases = [
+            ("dev", "DEV", true),
+            ("dev", "develop", false),
+            ("release/*", "release/1.2", true),
+            ("release/*", "release/", true),
+            ("release/*", "hotfix/release/1.2", false),
+            ("*-stable", "1.2-stable", true),
+            ("*-stable", "1.2-stable-old", false),
+            ("*/*", "team/feature/login", true),
+            ("a*b*c", "axxbyyc", true),
+            ("a*b*c", "axxcyyb", false),
+            ("release/1.?", "release/1.2", true),
+            ("release/1.?", "release/1.", false),
+            ("release/1.?", "release/1.10", false),
+            ("hotfix-??*", "hotfix-42", true),
+            ("hotfix-??*", "hotfix-4", false),
+        ];
+        for (pattern, name, expected) in cases {
+            assert_eq!(glob_match(pattern, name), expected, "{} ~ {}", pattern, name);
+        }
+    }
+
+    #[test]
+    fn regex_rule_resolves_channel_and_version() {
+        let rules = [tag_rule("live", "^live-(?P<version>.+)$", "{channel} {version}")];
+        let resolver = ChannelResolver::new(&rules);
+
+        let entry = resolver.resolve_tag("LIVE-7.3-hotfix").unwrap();
+        assert_eq!(entry.channel, "live");
+        assert_eq!(entry.version.as_deref(), Some("7.3-hotfix"));
+        assert_eq!(entry.display_name, "live 7.3-hotfix");
+        assert!(resolver.resolve_tag("stage-7.3").is_none());
+    }
+
+    #[test]
+    fn tag_without_version_shows_its_name() {
+        let rules = [tag_rule("hotfix", "hotfix", "{channel}-{version}")];
+        let entry = ChannelResolver::new(&rules).resolve_tag("hotfix-urgent").unwrap();
+        assert_eq!(entry.version, None);
+        assert_eq!(entry.display_name, "hotfix-urgent");
+    }
+
+    #[test]
+    fn invalid_rules_are_rejected() {
+        assert!(validate(&[tag_rule("live", "live-(", "{channel}")]).unwrap_err().contains("Invalid tag pattern for 'live'"));
+        assert!(validate(&[tag_rule(" ", "live", "{channel}")]).is_err());
+        assert!(validate(&[branch_rule("release", " ")]).is_err());
+        assert!(validate(&default_rules()).is_ok());
+    }
+
+    #[test]
+    fn resolver_skips_invalid_regex() {
+        let rules = [tag_rule("broken", "(", "{channel}"), tag_rule("qa", "qa", "{channel}-{version}")];
+        let entry = ChannelResolver::new(&rules).resolve_tag("qa-1.2.0").unwrap();
+        assert_eq!(entry.channel, "qa");
+        assert_eq!(entry.display_name, "qa-1.2.0");
+    }
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let rules = [tag_rule("dev", "dev", "{channel}-{version}"), tag_rule("any", "dev|qa", "{channel}-{version}")];
+        let resolver = ChannelResolver::new(&rules);
+        assert_eq!(resolver.resolve_tag("qa-dev-1.0").unwrap().channel, "dev");
+        assert_eq!(resolver.resolve_tag("qa-1.0").unwrap().channel, "any");
+    }
+
+    #[test]
+    fn branches_list_in_rule_order_then_by_name() {
+        let rules = [branch_rule("release", "release/*"), branch_rule("dev", "dev")];
+        let branches = ["dev", "release/2.0", "main", "release/1.9"].map(String::from);
+        let entries = ChannelResolver::new(&rules).resolve_branches(&branches);
+        let tags: Vec<&str> = entries.iter().map(|e| e.original_tag.as_str()).collect();
+        assert_eq!(tags, ["BRANCH:release/1.9", "BRANCH:release/2.0", "BRANCH:dev"]);
+        assert_eq!(entries[0].display_name, "release-release/1.9");
+    }
+}
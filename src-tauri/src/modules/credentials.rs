@@ -0,0 +1,287 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use crate::modules::types::{CredentialKind, CredentialSummary};
+
+/// Set on git's environment so the app binary, started again as `GIT_ASKPASS`,
+/// knows it should answer a prompt from the vault in this directory.
+pub const ASKPASS_VAULT_ENV: &str = "REPOHUB_ASKPASS_VAULT";
+
+const VAULT_FILE: &str = "credentials.vault";
+const NONCE_LEN: usize = 12;
+
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+const KEYCHAIN_SERVICE: &str = "RepoHub";
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+const KEYCHAIN_ACCOUNT: &str = "credential-vault";
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const KEY_FILE: &str = "credentials.key";
+
+static VAULT: OnceCell<Mutex<CredentialVault>> = OnceCell::new();
+
+/// An HTTPS token for one host. Only ever leaves the vault towards git.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HostCredential {
+    pub host: String,
+    pub username: String,
+    pub token: String,
+}
+
+/// Per-host HTTPS credentials, stored AES-256-GCM encrypted in the app data directory.
+/// The key lives in the OS keychain on macOS and Windows, and in a separate
+/// owner-only file elsewhere.
+struct CredentialVault {
+    dir: PathBuf,
+    askpass: Option<PathBuf>,
+    entries: Vec<HostCredential>,
+}
+
+impl CredentialVault {
+    fn open(dir: &Path) -> Result<Self, String> {
+        let path = dir.join(VAULT_FILE);
+        let entries = if path.exists() {
+            let data = std::fs::read(&path).map_err(|e| format!("Failed to read credential vault: {}", e))?;
+            decrypt(&load_or_create_key(dir)?, &data)?
+        } else {
+            Vec::new()
+        };
+
+        Ok(CredentialVault { dir: dir.to_path_buf(), askpass: None, entries })
+    }
+
+    fn save(&self) -> Result<(), String> {
+        std::fs::create_dir_all(&self.dir).map_err(|e| format!("Failed to create app data directory: {}", e))?;
+        let data = encrypt(&load_or_create_key(&self.dir)?, &self.entries)?;
+        std::fs::write(self.dir.join(VAULT_FILE), data).map_err(|e| format!("Failed to write credential vault: {}", e))
+    }
+
+    fn get(&self, host: &str) -> Option<&HostCredential> {
+        self.entries.iter().find(|c| c.host.eq_ignore_ascii_case(host))
+    }
+
+    fn summaries(&self) -> Vec<CredentialSummary> {
+        self.entries
+            .iter()
+            .map(|c| CredentialSummary { host: c.host.clone(), username: c.username.clone() })
+            .collect()
+    }
+
+    /// The username or token for a prompt like `Password for 'https://user@host': `.
+    fn answer(&self, prompt: &str) -> Option<String> {
+        let url = prompt.split('\'').nth(1)?;
+        let credential = self.get(&host_of(url)?)?;
+        if prompt.starts_with("Username") {
+            Some(credential.username.clone())
+        } else if prompt.starts_with("Password") {
+            Some(credential.token.clone())
+        } else {
+            None
+        }
+    }
+}
+
+/// Loads the vault from the app data directory and makes git ask it for HTTPS
+/// credentials from now on. A vault that cannot be decrypted is reported and
+/// replaced by an empty one on the next save.
+pub fn init(app_data_dir: &Path) {
+    let mut vault = CredentialVault::open(app_data_dir).unwrap_or_else(|e| {
+        println!("[credentials] {}", e);
+        CredentialVault { dir: app_data_dir.to_path_buf(), askpass: None, entries: Vec::new() }
+    });
+    vault.askpass = std::env::current_exe().ok();
+    VAULT.set(Mutex::new(vault)).ok();
+}
+
+fn with_vault<T>(f: impl FnOnce(&mut CredentialVault) -> Result<T, String>) -> Result<T, String> {
+    let vault = VAULT.get().ok_or("Credential vault is not initialized")?;
+    let mut vault = vault.lock().unwrap();
+    f(&mut vault)
+}
+
+pub fn list() -> Result<Vec<CredentialSummary>, String> {
+    with_vault(|vault| Ok(vault.summaries()))
+}
+
+pub fn lookup(host: &str) -> Option<HostCredential> {
+    with_vault(|vault| Ok(vault.get(host).cloned())).ok().flatten()
+}
+
+/// Adds or replaces the credential for `host`.
+pub fn store(host: &str, username: &str, token: &str) -> Result<Vec<CredentialSummary>, String> {
+    let host = host.trim().to_lowercase();
+    if host.is_empty() || host.contains('/') {
+        return Err("Enter a host name like github.com".to_string());
+    }
+    if token.is_empty() {
+        return Err("Token cannot be empty".to_string());
+    }
+
+    with_vault(|vault| {
+        vault.entries.retain(|c| c.host != host);
+        vault.entries.push(HostCredential { host, username: username.trim().to_string(), token: token.to_string() });
+        vault.entries.sort_by(|a, b| a.host.cmp(&b.host));
+        vault.save()?;
+        Ok(vault.summaries())
+    })
+}
+
+pub fn remove(host: &str) -> Result<Vec<CredentialSummary>, String> {
+    with_vault(|vault| {
+        vault.entries.retain(|c| !c.host.eq_ignore_ascii_case(host));
+        vault.save()?;
+        Ok(vault.summaries())
+    })
+}
+
+pub fn clear() -> Result<(), String> {
+    with_vault(|vault| {
+        vault.entries.clear();
+        vault.save()
+    })
+}
+
+/// Environment for every git invocation: the app itself as `GIT_ASKPASS`, and for
+/// hosts in the vault an empty `credential.helper`, so a stale token from the
+/// user's global helper does not win over the one saved here.
+pub fn git_env() -> Vec<(String, String)> {
+    let Ok((askpass, dir, hosts)) = with_vault(|vault| {
+        let hosts: Vec<String> = vault.entries.iter().map(|c| c.host.clone()).collect();
+        Ok((vault.askpass.clone(), vault.dir.clone(), hosts))
+    }) else {
+        return Vec::new();
+    };
+    let Some(askpass) = askpass else {
+        return Vec::new();
+    };
+
+    let mut env = vec![
+        ("GIT_ASKPASS".to_string(), askpass.to_string_lossy().to_string()),
+        (ASKPASS_VAULT_ENV.to_string(), dir.to_string_lossy().to_string()),
+        ("GIT_CONFIG_COUNT".to_string(), hosts.len().to_string()),
+    ];
+    for (i, host) in hosts.iter().enumerate() {
+        env.push((format!("GIT_CONFIG_KEY_{}", i), format!("credential.https://{}.helper", host)));
+        env.push((format!("GIT_CONFIG_VALUE_{}", i), String::new()));
+    }
+    env
+}
+
+/// Entry point when git runs the app binary as its askpass helper: prints the
+/// answer to the prompt in the first argument. Returns the exit code, or `None`
+/// for a normal app start.
+pub fn run_askpass() -> Option<i32> {
+    let dir = std::env::var_os(ASKPASS_VAULT_ENV)?;
+    let prompt = std::env::args().nth(1).unwrap_or_default();
+
+    // Failing makes git report "could not read Username/Password", which the app
+    // turns into a credential request for the frontend.
+    let answer = CredentialVault::open(Path::new(&dir)).ok().and_then(|vault| vault.answer(&prompt));
+    match answer {
+        Some(answer) => {
+            println!("{}", answer);
+            Some(0)
+        }
+        None => Some(1),
+    }
+}
+
+/// Host (with port, without user) of an HTTPS, SSH or scp-style remote URL.
+pub fn host_of(url: &str) -> Option<String> {
+    let rest = match url.split_once("://") {
+        Some((_, rest)) => rest,
+        None => {
+            // scp-style `git@host:owner/repo.git`; a local path or `C:\...` has no host.
+            let (authority, _) = url.split_once(':')?;
+            if authority.len() < 2 || authority.contains(['/', '\\']) {
+                return None;
+            }
+            authority
+        }
+    };
+    let authority = rest.split('/').next()?;
+    let host = authority.rsplit('@').next()?.to_lowercase();
+    if host.is_empty() { None } else { Some(host) }
+}
+
+pub fn credential_kind(url: &str) -> Option<CredentialKind> {
+    match url.split_once("://") {
+        Some((scheme, _)) => match scheme {
+            "https" | "http" => Some(CredentialKind::Https),
+            "ssh" | "git+ssh" | "ssh+git" => Some(CredentialKind::Ssh),
+            _ => None,
+        },
+        None => host_of(url).map(|_| CredentialKind::Ssh),
+    }
+}
+
+fn encrypt(key: &[u8], entries: &[HostCredential]) -> Result<Vec<u8>, String> {
+    let cipher = Aes256Gcm::new_from_slice(key).map_err(|e| e.to_string())?;
+    let plaintext = serde_json::to_vec(entries).map_err(|e| e.to_string())?;
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext.as_slice())
+        .map_err(|_| "Failed to encrypt credential vault".to_string())?;
+
+    let mut data = nonce.to_vec();
+    data.extend(ciphertext);
+    Ok(data)
+}
+
+fn decrypt(key: &[u8], data: &[u8]) -> Result<Vec<HostCredential>, String> {
+    if data.len() < NONCE_LEN {
+        return Err("Credential vault is corrupted".to_string());
+    }
+    let cipher = Aes256Gcm::new_from_slice(key).map_err(|e| e.to_string())?;
+    let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Credential vault could not be decrypted with the stored key".to_string())?;
+    serde_json::from_slice(&plaintext).map_err(|e| format!("Credential vault is corrupted: {}", e))
+}
+
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+fn load_or_create_key(_dir: &Path) -> Result<Vec<u8>, String> {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+
+    let entry = keyring::Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_ACCOUNT)
+        .map_err(|e| format!("Failed to open the keychain: {}", e))?;
+    match entry.get_password() {
+        Ok(encoded) => STANDARD.decode(encoded).map_err(|e| format!("Vault key in the keychain is invalid: {}", e)),
+        Err(keyring::Error::NoEntry) => {
+            let key = Aes256Gcm::generate_key(OsRng);
+            entry
+                .set_password(&STANDARD.encode(key))
+                .map_err(|e| format!("Failed to store the vault key in the keychain: {}", e))?;
+            Ok(key.to_vec())
+        }
+        Err(e) => Err(format!("Failed to read the vault key from the keychain: {}", e)),
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn load_or_create_key(dir: &Path) -> Result<Vec<u8>, String> {
+    use std::io::Write;
+
+    let path = dir.join(KEY_FILE);
+    if path.exists() {
+        return std::fs::read(&path).map_err(|e| format!("Failed to read the vault key: {}", e));
+    }
+
+    let key = Aes256Gcm::generate_key(OsRng);
+    std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(&path)
+        .and_then(|mut file| file.write_all(&key))
+        .map_err(|e| format!("Failed to write the vault key: {}", e))?;
+    Ok(key.to_vec())
+}
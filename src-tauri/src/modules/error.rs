@@ -0,0 +1,168 @@
+use serde::Serialize;
+use specta::Type;
+use std::fmt;
+
+/// A failed git invocation, classified from its stderr so the frontend can
+/// show an actionable message instead of a generic failure.
+#[derive(Serialize, Type, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum GitError {
+    /// The remote wants credentials that are missing or were rejected.
+    AuthRequired { exit_code: Option<i32>, stderr: String },
+    /// DNS lookup, connection or TLS handshake with the remote failed.
+    HostUnreachable { exit_code: Option<i32>, stderr: String },
+    /// The remote answered but has no repository at that URL (or hides it from us).
+    RepoNotFound { exit_code: Option<i32>, stderr: String },
+    DiskFull { exit_code: Option<i32>, stderr: String },
+    /// The repository is owned by another user and is not listed in `safe.directory`.
+    DubiousOwnership { exit_code: Option<i32>, stderr: String },
+    /// `.git/index.lock` exists, left behind by a crashed or still running git process.
+    IndexLocked { exit_code: Option<i32>, stderr: String },
+    /// Local modifications would be overwritten by the requested checkout.
+    LocalChangesConflict { exit_code: Option<i32>, stderr: String },
+    /// The repository uses Git LFS but `git-lfs` is not installed.
+    LfsMissing,
+    /// git could not be started at all, e.g. it is not in PATH.
+    SpawnFailed { message: String },
+    /// git ran longer than the command's timeout and was killed.
+    TimedOut { seconds: u64 },
+    /// The selected backend cannot do what was asked, e.g. sparse checkout with gix.
+    Unsupported { operation: String },
+    Cancelled,
+    Other { exit_code: Option<i32>, stderr: String },
+}
+
+impl GitError {
+    /// Classifies a non-zero git exit from its stderr. Checks run from the most
+    /// specific pattern to the least, since e.g. auth failures also mention the URL.
+    pub fn from_failure(exit_code: Option<i32>, stderr: String) -> GitError {
+        let lower = stderr.to_lowercase();
+        let matches = |patterns: &[&str]| patterns.iter().any(|p| lower.contains(p));
+
+        if matches(&["detected dubious ownership", "safe.directory"]) {
+            GitError::DubiousOwnership { exit_code, stderr }
+        } else if matches(&["index.lock", "another git process seems to be running"]) {
+            GitError::IndexLocked { exit_code, stderr }
+        } else if matches(&["no space left on device", "disk quota exceeded", "not enough space on the disk"]) {
+            GitError::DiskFull { exit_code, stderr }
+        } else if matches(&[
+            "would be overwritten by checkout",
+            "would be overwritten by merge",
+            "please commit your changes or stash them",
+            "untracked working tree files would be",
+        ]) {
+            GitError::LocalChangesConflict { exit_code, stderr }
+        } else if matches(&[
+            "authentication failed",
+            "could not read username",
+            "could not read password",
+            "terminal prompts disabled",
+            "permission denied (publickey",
+            "invalid username or password",
+            "http basic: access denied",
+        ]) {
+            GitError::AuthRequired { exit_code, stderr }
+        } else if matches(&[
+            "repository not found",
+            "does not appear to be a git repository",
+            "could not be found",
+            "remote: not found",
+            "error: 404",
+        ]) {
+            GitError::RepoNotFound { exit_code, stderr }
+        } else if matches(&[
+            "could not resolve host",
+            "could not resolve hostname",
+            "failed to connect",
+            "connection timed out",
+            "connection refused",
+            "network is unreachable",
+            "operation timed out",
+            "ssl certificate problem",
+        ]) {
+            GitError::HostUnreachable { exit_code, stderr }
+        } else {
+            GitError::Other { exit_code, stderr }
+        }
+    }
+
+    pub fn stderr(&self) -> Option<&str> {
+        match self {
+            GitError::AuthRequired { stderr, .. }
+            | GitError::HostUnreachable { stderr, .. }
+            | GitError::RepoNotFound { stderr, .. }
+            | GitError::DiskFull { stderr, .. }
+            | GitError::DubiousOwnership { stderr, .. }
+            | GitError::IndexLocked { stderr, .. }
+            | GitError::LocalChangesConflict { stderr, .. }
+            | GitError::Other { stderr, .. } => Some(stderr),
+            GitError::LfsMissing
+            | GitError::SpawnFailed { .. }
+            | GitError::TimedOut { .. }
+            | GitError::Unsupported { .. }
+            | GitError::Cancelled => None,
+        }
+    }
+}
+
+impl fmt::Display for GitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GitError::AuthRequired { .. } => write!(f, "Authentication required. Check your credentials for this remote."),
+            GitError::HostUnreachable { .. } => write!(f, "Could not reach the remote host. Check your network connection."),
+            GitError::RepoNotFound { .. } => write!(f, "Repository not found. Check the URL and your access rights."),
+            GitError::DiskFull { .. } => write!(f, "Not enough disk space."),
+            GitError::DubiousOwnership { .. } => write!(f, "Git refuses to use this repository because it is owned by another user. Add it to safe.directory."),
+            GitError::IndexLocked { .. } => write!(f, "Another git process is using this repository (index.lock exists)."),
+            GitError::LocalChangesConflict { .. } => write!(f, "Local changes would be overwritten."),
+            GitError::LfsMissing => write!(f, "This repository uses Git LFS, but git-lfs is not installed. Install it from https://git-lfs.com and try again."),
+            GitError::SpawnFailed { message } => write!(f, "Failed to run git: {}", message),
+            GitError::TimedOut { seconds } => write!(f, "git did not finish within {} seconds", seconds),
+            GitError::Unsupported { operation } => write!(f, "The built-in git backend does not support {}", operation),
+            GitError::Cancelled => write!(f, "Operation cancelled"),
+            GitError::Other { exit_code, stderr } => {
+                let detail = stderr.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or("").trim();
+                match exit_code {
+                    Some(code) => write!(f, "git exited with code {}: {}", code, detail),
+                    None => write!(f, "git was terminated: {}", detail),
+                }
+            }
+        }
+    }
+}
+
+/// Error returned by commands that run git: either a classified git failure
+/// or a plain message for everything else (unknown repository, bad input, ...).
+#[derive(Serialize, Type, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum CommandError {
+    Git { error: GitError },
+    Message { message: String },
+}
+
+impl From<GitError> for CommandError {
+    fn from(error: GitError) -> Self {
+        CommandError::Git { error }
+    }
+}
+
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        CommandError::Message { message }
+    }
+}
+
+impl From<&str> for CommandError {
+    fn from(message: &str) -> Self {
+        CommandError::Message { message: message.to_string() }
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandError::Git { error } => write!(f, "{}", error),
+            CommandError::Message { message } => write!(f, "{}", message),
+        }
+    }
+}
@@ -1,13 +1,31 @@
-use std::process::Output;
-use std::io::{BufRead, BufReader};
-use std::process::Stdio;
-use tokio::sync::mpsc;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead};
+use tokio::sync::{mpsc, watch};
+use crate::modules::error::GitError;
+use crate::modules::git_command::{GitCommand, NETWORK_TIMEOUT};
+use crate::modules::types::{BackupInfo, ChangelogCommit, CleanMode, CleanPolicy, CloneOptions, HeadInfo, HeadRef, TagDetails, TagRef, TagSortMode};
 
-#[cfg(windows)]
-use std::os::windows::process::CommandExt;
+/// Commands that write the working tree leave LFS pointers in place; the content is
+/// downloaded afterwards by `lfs_fetch_and_checkout`, which can report progress.
+const SKIP_LFS_SMUDGE: &[(&str, &str)] = &[("GIT_LFS_SKIP_SMUDGE", "1")];
 
-#[cfg(windows)]
-const CREATE_NO_WINDOW: u32 = 0x08000000;
+/// Where backups made before a version switch are kept.
+pub const BACKUP_REFS: &str = "refs/repohub/backups/";
+
+/// Backups are commits, so they need an identity even when the user has none configured.
+const BACKUP_IDENTITY: &[(&str, &str)] = &[
+    ("GIT_AUTHOR_NAME", "RepoHub"),
+    ("GIT_AUTHOR_EMAIL", "repohub@localhost"),
+    ("GIT_COMMITTER_NAME", "RepoHub"),
+    ("GIT_COMMITTER_EMAIL", "repohub@localhost"),
+];
+
+/// git-lfs only draws its progress meter on a terminal unless this is set.
+const FORCE_LFS_PROGRESS: &[(&str, &str)] = &[("GIT_LFS_FORCE_PROGRESS", "1")];
+
+/// How many trailing stderr lines of a progress-reporting command are kept for errors.
+const MAX_STDERR_LINES: usize = 50;
 
 #[derive(Clone, Debug)]
 pub struct CloneProgress {
@@ -17,138 +35,431 @@ pub struct CloneProgress {
     pub total_objects: Option<u32>,
     pub received_bytes: Option<f64>,
     pub speed: Option<String>,
+    pub submodule: Option<String>,
 }
 
 pub struct Git;
 
 impl Git {
-    pub async fn run_command(work_dir: &str, command: &str) -> (bool, Output) {
-        let program = "git";
-        let args = command.split_whitespace().collect::<Vec<&str>>();
-
-        #[cfg(windows)]
-        let output = tokio::process::Command::new(program)
-            .args(&args)
-            .current_dir(work_dir)
-            .creation_flags(CREATE_NO_WINDOW)
+    pub async fn clone(target_dir: &str, remote_url: &str) -> Result<(), GitError> {
+        println!("[Git::clone] Starting clone: {} -> {}", remote_url, target_dir);
+
+        GitCommand::new(".")
+            .args(["clone", remote_url, target_dir])
+            .no_timeout()
             .output()
-            .await
-            .unwrap();
+            .await?;
+        Ok(())
+    }
+
+    pub async fn clone_with_progress(
+        target_dir: &str, 
+        remote_url: &str,
+        options: &CloneOptions,
+        progress_tx: mpsc::Sender<CloneProgress>,
+        cancel_rx: watch::Receiver<bool>,
+    ) -> Result<(), GitError> {
+        println!("[Git::clone_with_progress] Starting clone: {} -> {} ({:?})", remote_url, target_dir, options);
+
+        let command = GitCommand::new(".")
+            .args(["clone", "--progress"])
+            .args(Self::clone_option_args(options))
+            .args([remote_url, target_dir])
+            .envs(SKIP_LFS_SMUDGE)
+            .ssh_key(options.ssh_key_path.as_deref())
+            .no_timeout();
+        Self::run_with_progress(command, progress_tx.clone(), cancel_rx.clone()).await?;
+
+        if !options.sparse_paths.is_empty() {
+            Self::sparse_checkout_set(target_dir, &options.sparse_paths).await?;
+        }
+
+        if Self::has_submodules(target_dir) {
+            Self::update_submodules(target_dir, options, progress_tx.clone(), cancel_rx.clone()).await?;
+        }
+
+        if Self::uses_lfs(target_dir) {
+            Self::lfs_fetch_and_checkout(target_dir, options, progress_tx, cancel_rx).await?;
+        }
+
+        Ok(())
+    }
+
+    fn clone_option_args(options: &CloneOptions) -> Vec<String> {
+        let mut args = Self::transfer_option_args(options);
+
+        if options.single_branch {
+            args.push("--single-branch".to_string());
+        } else if options.depth.is_some() {
+            // --depth implies --single-branch, which would hide the dev/qa branches.
+            args.push("--no-single-branch".to_string());
+        }
+        if let Some(branch) = options.branch.as_ref().filter(|b| !b.is_empty()) {
+            args.push("-b".to_string());
+            args.push(branch.clone());
+        }
+        if !options.sparse_paths.is_empty() {
+            // Only top-level files get checked out until the cone is set after cloning.
+            args.push("--sparse".to_string());
+        }
+
+        args
+    }
+
+    /// Arguments shared by `clone` and `fetch` that shape how much gets transferred.
+    fn transfer_option_args(options: &CloneOptions) -> Vec<String> {
+        let mut args = Vec::new();
+
+        if let Some(depth) = options.depth {
+            args.push(format!("--depth={}", depth));
+        }
+        if options.filter_blobs {
+            args.push("--filter=blob:none".to_string());
+        }
+        if let Some(jobs) = options.jobs {
+            args.push(format!("--jobs={}", jobs));
+        }
+
+        args
+    }
+
+    pub async fn fetch_with_progress(
+        work_dir: &str,
+        options: &CloneOptions,
+        progress_tx: mpsc::Sender<CloneProgress>,
+        cancel_rx: watch::Receiver<bool>,
+    ) -> Result<(), GitError> {
+        let command = GitCommand::new(work_dir)
+            .args(["fetch", "--progress"])
+            .args(Self::transfer_option_args(options))
+            .arg("origin")
+            .ssh_key(options.ssh_key_path.as_deref())
+            .no_timeout();
+        Self::run_with_progress(command, progress_tx, cancel_rx).await
+    }
+
+    /// Continues an interrupted clone in `work_dir`: re-initializes it if the partial
+    /// `.git` is missing, fetches the rest from origin and checks out the default branch.
+    pub async fn resume_clone(
+        work_dir: &str,
+        remote_url: &str,
+        options: &CloneOptions,
+        progress_tx: mpsc::Sender<CloneProgress>,
+        cancel_rx: watch::Receiver<bool>,
+    ) -> Result<(), GitError> {
+        if let Err(e) = std::fs::create_dir_all(work_dir) {
+            println!("[Git::resume_clone] Failed to create {}: {}", work_dir, e);
+            return Err(GitError::SpawnFailed { message: e.to_string() });
+        }
+
+        // Checked on disk rather than with rev-parse, which would happily
+        // report an enclosing repository the root path might live in.
+        if !Path::new(work_dir).join(".git").exists() {
+            GitCommand::new(work_dir).arg("init").output().await?;
+        }
+
+        if Self::remote_url(work_dir).await.ok().filter(|u| !u.is_empty()).is_none() {
+            GitCommand::new(work_dir).args(["remote", "add", "origin", remote_url]).output().await?;
+        }
+
+        Self::fetch_with_progress(work_dir, options, progress_tx.clone(), cancel_rx.clone()).await?;
+
+        match options.branch.as_ref().filter(|b| !b.is_empty()) {
+            Some(branch) => Self::checkout_requested_ref(work_dir, branch).await?,
+            None => Self::checkout_default_branch(work_dir, options).await?,
+        }
+
+        if !options.sparse_paths.is_empty() {
+            Self::sparse_checkout_set(work_dir, &options.sparse_paths).await?;
+        }
+
+        if Self::has_submodules(work_dir) {
+            Self::update_submodules(work_dir, options, progress_tx.clone(), cancel_rx.clone()).await?;
+        }
+
+        if Self::uses_lfs(work_dir) {
+            Self::lfs_fetch_and_checkout(work_dir, options, progress_tx, cancel_rx).await?;
+        }
+
+        Ok(())
+    }
+
+    pub fn has_submodules(work_dir: &str) -> bool {
+        Path::new(work_dir).join(".gitmodules").exists()
+    }
+
+    /// Brings every submodule, recursively, to the commit the superproject records,
+    /// discarding local changes inside them. URLs are re-synced first because they
+    /// may differ between versions of `.gitmodules`.
+    pub async fn update_submodules(
+        work_dir: &str,
+        options: &CloneOptions,
+        progress_tx: mpsc::Sender<CloneProgress>,
+        cancel_rx: watch::Receiver<bool>,
+    ) -> Result<(), GitError> {
+        GitCommand::new(work_dir).args(["submodule", "sync", "--recursive"]).no_timeout().output().await?;
+
+        let command = GitCommand::new(work_dir)
+            .args(["submodule", "update", "--init", "--recursive", "--force", "--progress"])
+            .envs(SKIP_LFS_SMUDGE)
+            .ssh_key(options.ssh_key_path.as_deref())
+            .no_timeout();
+        Self::run_with_progress(command, progress_tx, cancel_rx).await
+    }
+
+    /// True when any submodule is uninitialized, conflicted, or checked out at a
+    /// different commit than the one the superproject records.
+    pub async fn submodules_out_of_sync(work_dir: &str) -> bool {
+        if !Self::has_submodules(work_dir) {
+            return false;
+        }
+
+        match GitCommand::new(work_dir).args(["submodule", "status", "--recursive"]).stdout().await {
+            Ok(status) => status
+                .lines()
+                .any(|line| line.starts_with(['-', '+', 'U'])),
+            Err(_) => true,
+        }
+    }
+
+    /// True when the checked-out `.gitattributes` routes any path through the LFS filter.
+    pub fn uses_lfs(work_dir: &str) -> bool {
+        std::fs::read_to_string(Path::new(work_dir).join(".gitattributes"))
+            .map(|attributes| {
+                attributes
+                    .lines()
+                    .filter(|line| !line.trim_start().starts_with('#'))
+                    .any(|line| line.contains("filter=lfs"))
+            })
+            .unwrap_or(false)
+    }
+
+    pub async fn is_lfs_installed() -> bool {
+        GitCommand::new(".").args(["lfs", "version"]).output().await.is_ok()
+    }
+
+    /// Downloads the LFS objects for HEAD and replaces the pointer files in the working
+    /// tree, as two separate steps so neither hides behind a silent smudge filter.
+    pub async fn lfs_fetch_and_checkout(
+        work_dir: &str,
+        options: &CloneOptions,
+        progress_tx: mpsc::Sender<CloneProgress>,
+        cancel_rx: watch::Receiver<bool>,
+    ) -> Result<(), GitError> {
+        if !Self::is_lfs_installed().await {
+            return Err(GitError::LfsMissing);
+        }
+
+        // Registers the smudge/clean filters for this repository in case
+        // `git lfs install` was never run globally on this machine.
+        GitCommand::new(work_dir).args(["lfs", "install", "--local"]).output().await?;
+
+        let fetch = GitCommand::new(work_dir)
+            .args(["lfs", "fetch"])
+            .envs(FORCE_LFS_PROGRESS)
+            .ssh_key(options.ssh_key_path.as_deref())
+            .no_timeout();
+        Self::run_with_progress(fetch, progress_tx.clone(), cancel_rx.clone()).await?;
 
-        #[cfg(not(windows))]
-        let output = tokio::process::Command::new(program)
-            .args(&args)
-            .current_dir(work_dir)
+        let checkout = GitCommand::new(work_dir)
+            .args(["lfs", "checkout"])
+            .envs(FORCE_LFS_PROGRESS)
+            .no_timeout();
+        Self::run_with_progress(checkout, progress_tx, cancel_rx).await
+    }
+
+    async fn checkout_default_branch(work_dir: &str, options: &CloneOptions) -> Result<(), GitError> {
+        // Only refreshes origin/HEAD; an older one is still usable if this fails.
+        GitCommand::new(work_dir)
+            .args(["remote", "set-head", "origin", "--auto"])
+            .ssh_key(options.ssh_key_path.as_deref())
+            .timeout(NETWORK_TIMEOUT)
             .output()
             .await
-            .unwrap();
+            .ok();
 
-        let has_error = !output.status.success();
-        (has_error, output)
+        let remote_head = GitCommand::new(work_dir)
+            .args(["symbolic-ref", "--short", "refs/remotes/origin/HEAD"])
+            .stdout()
+            .await?
+            .trim()
+            .to_string();
+        let branch = remote_head.trim_start_matches("origin/");
+        GitCommand::new(work_dir)
+            .args(["checkout", "-f", "-B", branch, &remote_head])
+            .envs(SKIP_LFS_SMUDGE)
+            .no_timeout()
+            .output()
+            .await?;
+        Ok(())
     }
 
-    pub async fn clone(target_dir: &str, remote_url: &str) -> bool {
-        let target = target_dir.to_string();
-        let url = remote_url.to_string();
-        
-        println!("[Git::clone] Starting clone: {} -> {}", url, target);
-        
-        let result = tokio::task::spawn_blocking(move || {
-            println!("[Git::clone] spawn_blocking started");
-            
-            #[cfg(windows)]
-            let output = std::process::Command::new("git")
-                .args(["clone", &url, &target])
-                .creation_flags(CREATE_NO_WINDOW)
-                .output();
-
-            #[cfg(not(windows))]
-            let output = std::process::Command::new("git")
-                .args(["clone", &url, &target])
-                .output();
-
-            println!("[Git::clone] git command finished");
-            output
-        }).await;
-
-        match result {
-            Ok(Ok(output)) => {
-                println!("[Git::clone] Success: {}", output.status.success());
-                if !output.status.success() {
-                    println!("[Git::clone] stderr: {}", String::from_utf8_lossy(&output.stderr));
-                }
-                output.status.success()
-            },
-            Ok(Err(e)) => {
-                println!("[Git::clone] Command error: {}", e);
-                false
-            },
-            Err(e) => {
-                println!("[Git::clone] Task error: {}", e);
-                false
-            },
+    /// Checks out what `clone -b` would have: the remote branch if one exists, otherwise the tag.
+    async fn checkout_requested_ref(work_dir: &str, branch: &str) -> Result<(), GitError> {
+        let branch_checkout = GitCommand::new(work_dir)
+            .args(["checkout", "-f", "-B", branch, &format!("origin/{}", branch)])
+            .envs(SKIP_LFS_SMUDGE)
+            .no_timeout()
+            .output()
+            .await;
+        if branch_checkout.is_ok() {
+            return Ok(());
         }
+
+        GitCommand::new(work_dir)
+            .args(["checkout", "-f", &format!("tags/{}", branch)])
+            .envs(SKIP_LFS_SMUDGE)
+            .no_timeout()
+            .output()
+            .await?;
+        Ok(())
     }
 
-    pub async fn clone_with_progress(
-        target_dir: &str, 
-        remote_url: &str,
-        progress_tx: mpsc::Sender<CloneProgress>
-    ) -> bool {
-        let target = target_dir.to_string();
-        let url = remote_url.to_string();
-        
-        println!("[Git::clone_with_progress] Starting clone: {} -> {}", url, target);
-        
-        let result = tokio::task::spawn_blocking(move || {
-            #[cfg(windows)]
-            let mut child = match std::process::Command::new("git")
-                .args(["clone", "--progress", &url, &target])
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .creation_flags(CREATE_NO_WINDOW)
-                .spawn() {
-                    Ok(child) => child,
-                    Err(e) => {
-                        println!("[Git::clone_with_progress] Spawn error: {}", e);
-                        return false;
-                    }
-                };
+    /// Runs a long git transfer command, forwarding parsed progress lines from its output
+    /// until it exits or `cancel_rx` flips to true, in which case the process is killed.
+    async fn run_with_progress(
+        command: GitCommand,
+        progress_tx: mpsc::Sender<CloneProgress>,
+        mut cancel_rx: watch::Receiver<bool>,
+    ) -> Result<(), GitError> {
+        let mut process = match command.spawn() {
+            Ok(process) => process,
+            Err(e) => {
+                println!("[Git::run_with_progress] Spawn error for `{}`: {}", command, e);
+                return Err(e);
+            }
+        };
 
-            #[cfg(not(windows))]
-            let mut child = match std::process::Command::new("git")
-                .args(["clone", "--progress", &url, &target])
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .spawn() {
-                    Ok(child) => child,
-                    Err(e) => {
-                        println!("[Git::clone_with_progress] Spawn error: {}", e);
-                        return false;
+        // Progress goes to stderr, but `submodule update` reports each checkout
+        // (`Submodule path '…': checked out`) on stdout, so both are read as lines.
+        let (line_tx, mut line_rx) = mpsc::channel::<String>(64);
+        Self::forward_lines(process.child.stdout.take().expect("Failed to capture stdout"), line_tx.clone());
+        Self::forward_lines(process.child.stderr.take().expect("Failed to capture stderr"), line_tx);
+        let work_dir = command.work_dir().to_string();
+        let time_limit = command.time_limit();
+        let reader_task = tokio::spawn(async move {
+            // Submodule clones print their own progress, one after another; remember
+            // which one is running so its progress lines can be attributed to it.
+            let mut current_submodule: Option<String> = None;
+            // Everything that is not a progress meter is kept to classify a failure.
+            let mut messages: Vec<String> = Vec::new();
+            while let Some(line) = line_rx.recv().await {
+                if let Some(path) = Self::parse_submodule_path(&line, &work_dir) {
+                    current_submodule = Some(path.clone());
+                    let _ = progress_tx.send(CloneProgress {
+                        stage: "Submodule".to_string(),
+                        progress: 0,
+                        received_objects: None,
+                        total_objects: None,
+                        received_bytes: None,
+                        speed: None,
+                        submodule: Some(path),
+                    }).await;
+                } else if let Some(mut progress) = Self::parse_git_progress(&line) {
+                    progress.submodule = current_submodule.clone();
+                    let _ = progress_tx.send(progress).await;
+                } else if !line.trim().is_empty() {
+                    if messages.len() == MAX_STDERR_LINES {
+                        messages.remove(0);
                     }
-                };
-
-            let stderr = child.stderr.take().expect("Failed to capture stderr");
-            let reader = BufReader::new(stderr);
+                    messages.push(line.trim_end().to_string());
+                }
+            }
+            messages.join("\n")
+        });
 
-            for line in reader.lines() {
-                if let Ok(line) = line {
-                    if let Some(progress) = Self::parse_git_progress(&line) {
-                        let _ = progress_tx.blocking_send(progress);
+        // A dropped sender makes `wait_for` return Err; the `true` pattern then
+        // disables the cancel branch instead of treating it as a cancellation.
+        tokio::select! {
+            status = process.child.wait() => {
+                let stderr = reader_task.await.unwrap_or_default();
+                match status {
+                    Ok(status) if status.success() => Ok(()),
+                    Ok(status) => {
+                        println!("[Git::run_with_progress] `{}` failed: {}", command, stderr);
+                        Err(GitError::from_failure(status.code(), stderr))
+                    }
+                    Err(e) => {
+                        println!("[Git::run_with_progress] Wait error: {}", e);
+                        Err(GitError::SpawnFailed { message: e.to_string() })
                     }
                 }
             }
+            true = async { cancel_rx.wait_for(|cancelled| *cancelled).await.is_ok() } => {
+                println!("[Git::run_with_progress] Cancelled, killing git and its child processes");
+                if let Err(e) = process.kill().await {
+                    println!("[Git::run_with_progress] Kill error: {}", e);
+                }
+                reader_task.abort();
+                Err(GitError::Cancelled)
+            }
+            _ = async {
+                match time_limit {
+                    Some(limit) => tokio::time::sleep(limit).await,
+                    None => std::future::pending().await,
+                }
+            } => {
+                println!("[Git::run_with_progress] `{}` timed out, killing git and its child processes", command);
+                if let Err(e) = process.kill().await {
+                    println!("[Git::run_with_progress] Kill error: {}", e);
+                }
+                reader_task.abort();
+                Err(GitError::TimedOut { seconds: time_limit.unwrap_or_default().as_secs() })
+            }
+        }
+    }
 
-            match child.wait() {
-                Ok(status) => status.success(),
-                Err(e) => {
-                    println!("[Git::clone_with_progress] Wait error: {}", e);
-                    false
+    /// Sends each line of `stream`, split by `read_progress_line`, until the stream ends
+    /// or the receiver is gone.
+    fn forward_lines<R: AsyncRead + Unpin + Send + 'static>(stream: R, line_tx: mpsc::Sender<String>) {
+        tokio::spawn(async move {
+            let mut reader = tokio::io::BufReader::new(stream);
+            let mut line = Vec::new();
+            while let Ok(true) = Self::read_progress_line(&mut reader, &mut line).await {
+                if line_tx.send(String::from_utf8_lossy(&line).into_owned()).await.is_err() {
+                    break;
                 }
             }
-        }).await;
+        });
+    }
+
+    /// Reads the next progress line into `line`. Progress meters redraw themselves with
+    /// `\r`, so that ends a line as well as `\n`. Returns false at end of stream.
+    async fn read_progress_line<R: AsyncBufRead + Unpin>(reader: &mut R, line: &mut Vec<u8>) -> std::io::Result<bool> {
+        line.clear();
+        loop {
+            let available = reader.fill_buf().await?;
+            if available.is_empty() {
+                return Ok(!line.is_empty());
+            }
+            if let Some(end) = available.iter().position(|b| *b == b'\r' || *b == b'\n') {
+                line.extend_from_slice(&available[..end]);
+                reader.consume(end + 1);
+                return Ok(true);
+            }
+            let len = available.len();
+            line.extend_from_slice(available);
+            reader.consume(len);
+        }
+    }
+
+    /// Extracts the submodule path from `Cloning into '<abs path>'...` or
+    /// `Submodule path '<path>': checked out ...`, relative to `work_dir`.
+    /// The top-level clone's own `Cloning into` line is not a submodule.
+    fn parse_submodule_path(line: &str, work_dir: &str) -> Option<String> {
+        let line = line.trim();
+        let rest = line.strip_prefix("Cloning into '")
+            .or_else(|| line.strip_prefix("Submodule path '"))?;
+        let path = &rest[..rest.find('\'')?];
+
+        let relative = path.strip_prefix(work_dir)
+            .unwrap_or(path)
+            .trim_start_matches(['/', '\\']);
+        if relative.is_empty() || work_dir == "." {
+            return None;
+        }
 
-        result.unwrap_or(false)
+        Some(relative.replace('\\', "/"))
     }
 
     fn parse_git_progress(line: &str) -> Option<CloneProgress> {
@@ -166,9 +477,14 @@ impl Git {
                 total_objects: None,
                 received_bytes: None,
                 speed: None,
+                submodule: None,
             });
         } else if line.contains("Compressing objects:") {
             return Self::parse_compressing_objects(line);
+        } else if line.contains("Downloading LFS objects:") {
+            return Self::parse_lfs_transfer(line, "LfsDownloading");
+        } else if line.contains("Checking out LFS objects:") {
+            return Self::parse_lfs_transfer(line, "LfsCheckout");
         }
         
         None
@@ -220,9 +536,25 @@ impl Git {
             total_objects: total,
             received_bytes: bytes,
             speed,
+            submodule: None,
         })
     }
 
+    /// git-lfs meters look like `Downloading LFS objects:  45% (9/20), 120 MB | 5.2 MB/s`,
+    /// the same shape as git's receiving line, so that parser does the work. LFS is its own
+    /// phase, so the percentage is reported as is rather than squeezed into the clone range.
+    fn parse_lfs_transfer(line: &str, stage: &str) -> Option<CloneProgress> {
+        let mut progress = Self::parse_receiving_objects(line)?;
+        progress.stage = stage.to_string();
+
+        if let Some(pct_idx) = line.find('%') {
+            let start = line[..pct_idx].rfind(|c: char| !c.is_ascii_digit()).map(|i| i + 1).unwrap_or(0);
+            progress.progress = line[start..pct_idx].trim().parse::<u32>().unwrap_or(0);
+        }
+
+        Some(progress)
+    }
+
     fn parse_resolving_deltas(line: &str) -> Option<CloneProgress> {
         let mut progress: u32 = 60;
 
@@ -240,6 +572,7 @@ impl Git {
             total_objects: None,
             received_bytes: None,
             speed: None,
+            submodule: None,
         })
     }
 
@@ -260,6 +593,7 @@ impl Git {
             total_objects: None,
             received_bytes: None,
             speed: None,
+            submodule: None,
         })
     }
 
@@ -285,228 +619,677 @@ impl Git {
         Some(num * multiplier as f64)
     }
 
-    pub async fn remote_branch_list(work_dir: &str) -> Option<Vec<String>> {
-        let (has_error, output) = Self::run_command(work_dir, "branch -r").await;
+    pub async fn remote_branch_list(work_dir: &str) -> Result<Vec<String>, GitError> {
+        let result = GitCommand::new(work_dir).args(["branch", "-r"]).stdout().await?;
 
-        if !has_error {
-            let result = String::from_utf8_lossy(&output.stdout);
-            let branches = result
-                .lines()
-                .map(|line| line.trim().to_string())
-                .collect::<Vec<String>>();
-            Some(branches)
-        } else {
-            None
-        }
-    }
-
-    /// Check if a specific remote branch exists (e.g., "dev", "qa")
-    pub async fn has_remote_branch(work_dir: &str, branch_name: &str) -> bool {
-        if let Some(branches) = Self::remote_branch_list(work_dir).await {
-            branches.iter().any(|b| {
-                let normalized = b.replace("origin/", "");
-                normalized.eq_ignore_ascii_case(branch_name)
-            })
-        } else {
-            false
-        }
+        let branches = result
+            .lines()
+            .map(|line| line.trim().to_string())
+            .collect::<Vec<String>>();
+        Ok(branches)
     }
 
-    /// Checkout to a remote branch with fetch and pull
-    pub async fn checkout_remote_branch(work_dir: &str, branch: &str, discard_all: bool) -> bool {
-        if discard_all {
-            Self::reset_hard(work_dir).await;
-        }
-        
-        Self::fetch(work_dir).await;
-        
-        let (has_error, _) = Self::run_command(work_dir, &format!("checkout {}", branch)).await;
+    /// Checks out the local `branch`, creating it from `origin/<branch>` if needed, then pulls.
+    pub async fn checkout_tracking_branch(work_dir: &str, branch: &str, options: &CloneOptions) -> Result<(), GitError> {
+        let existing = GitCommand::new(work_dir)
+            .args(["checkout", branch])
+            .envs(SKIP_LFS_SMUDGE)
+            .no_timeout()
+            .output()
+            .await;
         
-        if has_error {
-            let (has_error2, _) = Self::run_command(
-                work_dir, 
-                &format!("checkout -b {} origin/{}", branch, branch)
-            ).await;
-            
-            if has_error2 {
-                let (has_error3, _) = Self::run_command(work_dir, &format!("checkout {}", branch)).await;
-                if !has_error3 {
-                    Self::run_command(work_dir, "pull").await;
-                }
-                return !has_error3;
-            }
+        if existing.is_err() {
+            GitCommand::new(work_dir)
+                .args(["checkout", "-b", branch, &format!("origin/{}", branch)])
+                .envs(SKIP_LFS_SMUDGE)
+                .no_timeout()
+                .output()
+                .await?;
         }
         
-        Self::run_command(work_dir, "pull").await;
+        GitCommand::new(work_dir)
+            .arg("pull")
+            .envs(SKIP_LFS_SMUDGE)
+            .ssh_key(options.ssh_key_path.as_deref())
+            .timeout(NETWORK_TIMEOUT)
+            .output()
+            .await?;
         
-        !has_error
+        Ok(())
+    }
+
+    pub async fn current_branch(work_dir: &str) -> Result<String, GitError> {
+        let branch = GitCommand::new(work_dir).args(["rev-parse", "--abbrev-ref", "HEAD"]).stdout().await?;
+        Ok(branch.trim().to_string())
     }
 
-    pub async fn current_branch(work_dir: &str) -> Option<String> {
-        let (has_error, output) = Self::run_command(work_dir, "rev-parse --abbrev-ref HEAD").await;
+    /// Reads what HEAD is on. A checked-out branch wins over tags at the same commit;
+    /// among several tags, the first one in `preferred_tags` is picked.
+    pub async fn head(work_dir: &str, preferred_tags: &[String]) -> Result<HeadInfo, GitError> {
+        let log = GitCommand::new(work_dir)
+            .args(["log", "-1", "--format=%H%x1f%s%x1f%cI", "HEAD"])
+            .stdout()
+            .await?;
+        let [commit, subject, date] = log.trim_end_matches('\n').split('\u{1f}').collect::<Vec<_>>()[..] else {
+            return Err(GitError::from_failure(None, format!("Unexpected log output: {}", log)));
+        };
 
-        if !has_error {
-            let branch = String::from_utf8_lossy(&output.stdout).trim().to_string();
-            Some(branch)
+        let branch = GitCommand::new(work_dir).args(["symbolic-ref", "--quiet", "--short", "HEAD"]).stdout().await.ok();
+        let reference = if let Some(name) = branch.map(|b| b.trim().to_string()).filter(|b| !b.is_empty()) {
+            let upstream = GitCommand::new(work_dir)
+                .args(["rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{upstream}"])
+                .stdout()
+                .await
+                .ok()
+                .map(|u| u.trim().to_string())
+                .filter(|u| !u.is_empty());
+            HeadRef::Branch { name, upstream }
         } else {
-            None
-        }
+            let tags = GitCommand::new(work_dir)
+                .args(["tag", "--points-at", "HEAD", "--sort=-creatordate"])
+                .stdout()
+                .await?;
+            let tags: Vec<&str> = tags.lines().map(str::trim).filter(|t| !t.is_empty()).collect();
+            let tag = tags.iter().find(|t| preferred_tags.iter().any(|p| p == *t)).or(tags.first());
+            match tag {
+                Some(name) => HeadRef::Tag { name: name.to_string() },
+                None => {
+                    let describe = GitCommand::new(work_dir).args(["describe", "--tags", "--always"]).stdout().await?;
+                    HeadRef::Detached { describe: describe.trim().to_string() }
+                }
+            }
+        };
+
+        Ok(HeadInfo { reference, commit: commit.to_string(), subject: subject.to_string(), date: date.to_string() })
     }
 
-    pub async fn remote_url(work_dir: &str) -> Option<String> {
-        let (has_error, output) = Self::run_command(work_dir, "config --get remote.origin.url").await;
+    /// The commit `refname` (`refs/tags/v1.0`, `refs/heads/main`) points at on origin,
+    /// peeled for annotated tags; `None` when origin does not have it.
+    pub async fn remote_commit(work_dir: &str, refname: &str, options: &CloneOptions) -> Result<Option<String>, GitError> {
+        let output = GitCommand::new(work_dir)
+            .args(["ls-remote", "origin", refname, &format!("{}^{{}}", refname)])
+            .ssh_key(options.ssh_key_path.as_deref())
+            .timeout(NETWORK_TIMEOUT)
+            .stdout()
+            .await?;
+        let refs: Vec<(&str, &str)> = output.lines().filter_map(|line| line.split_once('\t')).collect();
+        Ok(refs
+            .iter()
+            .find(|(_, name)| name.ends_with("^{}"))
+            .or_else(|| refs.first())
+            .map(|(commit, _)| commit.to_string()))
+    }
 
-        if !has_error {
-            let url = String::from_utf8_lossy(&output.stdout).trim().to_string();
-            Some(url)
-        } else {
-            None
-        }
+    /// The configured `user.name`, for recording who did something in the app.
+    pub async fn user_name(work_dir: &str) -> Option<String> {
+        let name = GitCommand::new(work_dir).args(["config", "user.name"]).stdout().await.ok()?;
+        Some(name.trim().to_string()).filter(|n| !n.is_empty())
+    }
+
+    pub async fn remote_url(work_dir: &str) -> Result<String, GitError> {
+        let url = GitCommand::new(work_dir).args(["config", "--get", "remote.origin.url"]).stdout().await?;
+        Ok(url.trim().to_string())
     }
 
     pub async fn is_git_directory(work_dir: &str) -> bool {
-        let (has_error, _) = Self::run_command(work_dir, "rev-parse --is-inside-work-tree").await;
-        !has_error
+        GitCommand::new(work_dir).args(["rev-parse", "--is-inside-work-tree"]).output().await.is_ok()
     }
 
     pub async fn is_git_in_path() -> bool {
-        let (has_error, _) = Self::run_command(".", "--version").await;
-        !has_error
+        GitCommand::new(".").arg("--version").output().await.is_ok()
     }
 
-    pub async fn reset_hard(work_dir: &str) -> bool {
-        let (has_error, _) = Self::run_command(work_dir, "reset --hard").await;
-        !has_error
+    pub async fn reset_hard(work_dir: &str) -> Result<(), GitError> {
+        GitCommand::new(work_dir).args(["reset", "--hard"]).envs(SKIP_LFS_SMUDGE).no_timeout().output().await?;
+        Ok(())
     }
 
-    pub async fn check_out(work_dir: &str, branch: &str, discard_all: bool) -> bool {
-        if discard_all {
-            Self::reset_hard(work_dir).await;
+    pub async fn reset_to(work_dir: &str, commit: &str) -> Result<(), GitError> {
+        GitCommand::new(work_dir).args(["reset", "--hard", commit]).envs(SKIP_LFS_SMUDGE).no_timeout().output().await?;
+        Ok(())
+    }
+
+    /// Changed and untracked paths from `git status`, as (modified, staged, untracked).
+    /// Untracked directories are listed once, with a trailing `/`.
+    pub async fn working_tree_changes(work_dir: &str) -> Result<(Vec<String>, Vec<String>, Vec<String>), GitError> {
+        let output = GitCommand::new(work_dir).args(["status", "--porcelain=v1", "-z"]).stdout().await?;
+
+        let (mut modified, mut staged, mut untracked) = (Vec::new(), Vec::new(), Vec::new());
+        let mut entries = output.split('\0');
+        while let Some(entry) = entries.next() {
+            if entry.len() < 4 {
+                continue;
+            }
+            let (status, path) = entry.split_at(3);
+            let (index, worktree) = (status.as_bytes()[0], status.as_bytes()[1]);
+            if index == b'R' || index == b'C' {
+                // The source path of a rename follows as its own entry.
+                entries.next();
+            }
+            match (index, worktree) {
+                (b'?', b'?') => untracked.push(path.to_string()),
+                (b'!', b'!') => {}
+                _ => {
+                    if index != b' ' {
+                        staged.push(path.to_string());
+                    }
+                    if worktree != b' ' {
+                        modified.push(path.to_string());
+                    }
+                }
+            }
         }
-        let (has_error, _) = Self::run_command(work_dir, &format!("checkout {}", branch)).await;
-        !has_error
+        Ok((modified, staged, untracked))
     }
 
-    pub async fn fetch(work_dir: &str) -> bool {
-        let (has_error, _) = Self::run_command(work_dir, "fetch").await;
-        !has_error
+    /// Commits only on HEAD and commits only on `upstream`, as `(ahead, behind)`.
+    pub async fn ahead_behind(work_dir: &str, upstream: &str) -> Result<(u32, u32), GitError> {
+        let output = GitCommand::new(work_dir)
+            .args(["rev-list", "--left-right", "--count"])
+            .arg(format!("HEAD...{}", upstream))
+            .stdout()
+            .await?;
+        let mut counts = output.split_whitespace().map(|n| n.parse().unwrap_or(0));
+        Ok((counts.next().unwrap_or(0), counts.next().unwrap_or(0)))
     }
 
-    pub async fn fetch_tags(work_dir: &str) -> bool {
-        let (has_error, _) = Self::run_command(work_dir, "fetch --tags").await;
-        !has_error
+    /// Tracked files whose local changes git has been told to ignore, with
+    /// `update-index --assume-unchanged` or `--skip-worktree`. A hard reset still
+    /// overwrites them. Sparse checkouts mark every file outside the cone as
+    /// skip-worktree, so only assume-unchanged files count there.
+    pub async fn hidden_tracked_files(work_dir: &str, sparse: bool) -> Result<Vec<String>, GitError> {
+        let output = GitCommand::new(work_dir).args(["ls-files", "-v", "-z"]).stdout().await?;
+        Ok(output
+            .split('\0')
+            .filter_map(|entry| {
+                let (tag, path) = entry.split_once(' ')?;
+                let hidden = tag.chars().all(|c| c.is_ascii_lowercase()) || (tag == "S" && !sparse);
+                hidden.then(|| path.to_string())
+            })
+            .collect())
     }
 
-    pub async fn get_tags(work_dir: &str, limit: usize) -> Option<Vec<String>> {
-        let (has_error, output) = Self::run_command(work_dir, "tag --sort=-creatordate").await;
+    /// Snapshots the working tree (tracked changes and untracked files that are not
+    /// ignored) as a commit on top of HEAD and keeps it under `BACKUP_REFS`. Works on
+    /// a copy of the index, so nothing in the checkout changes. Returns the ref name.
+    pub async fn create_backup(work_dir: &str, message: &str) -> Result<String, GitError> {
+        let index = GitCommand::new(work_dir).args(["rev-parse", "--git-path", "index"]).stdout().await?;
+        let index = Path::new(work_dir).join(index.trim());
+        let stamp = chrono::Local::now();
+        let temp_index = std::env::temp_dir().join(format!("repohub-backup-{}-{}.index", std::process::id(), stamp.timestamp_nanos_opt().unwrap_or_default()));
+        if index.exists() {
+            std::fs::copy(&index, &temp_index).map_err(|e| GitError::from_failure(None, e.to_string()))?;
+        }
+        let temp_index_path = temp_index.to_string_lossy().to_string();
 
-        if !has_error {
-            let result = String::from_utf8_lossy(&output.stdout);
-            let tags: Vec<String> = result
-                .lines()
-                .take(limit)
-                .map(|line| line.trim().to_string())
-                .filter(|t| !t.is_empty())
-                .collect();
-            Some(tags)
-        } else {
-            None
+        let snapshot = async {
+            GitCommand::new(work_dir)
+                .args(["add", "--all"])
+                .env("GIT_INDEX_FILE", &temp_index_path)
+                .no_timeout()
+                .output()
+                .await?;
+            let tree = GitCommand::new(work_dir)
+                .args(["write-tree"])
+                .env("GIT_INDEX_FILE", &temp_index_path)
+                .stdout()
+                .await?;
+            let commit = GitCommand::new(work_dir)
+                .args(["commit-tree", tree.trim(), "-p", "HEAD", "-m", message])
+                .envs(BACKUP_IDENTITY)
+                .stdout()
+                .await?;
+            Ok::<String, GitError>(commit.trim().to_string())
+        }
+        .await;
+        std::fs::remove_file(&temp_index).ok();
+        let commit = snapshot?;
+
+        let existing = Self::list_backups(work_dir).await?;
+        let base_name = format!("{}{}", BACKUP_REFS, stamp.format("%Y%m%d-%H%M%S"));
+        let mut name = base_name.clone();
+        let mut n = 2;
+        while existing.iter().any(|b| b.name == name) {
+            name = format!("{}-{}", base_name, n);
+            n += 1;
         }
+        GitCommand::new(work_dir).args(["update-ref", &name, &commit]).output().await?;
+        Ok(name)
     }
 
-    pub async fn get_filtered_tags(work_dir: &str, limit: usize) -> Option<Vec<(String, String)>> {
-        let (has_error, output) = Self::run_command(work_dir, "tag --sort=-creatordate").await;
+    /// Backups of this repository, newest first.
+    pub async fn list_backups(work_dir: &str) -> Result<Vec<BackupInfo>, GitError> {
+        let output = GitCommand::new(work_dir)
+            .args([
+                "for-each-ref",
+                "--sort=-creatordate",
+                "--format=%(refname)%1f%(objectname)%1f%(parent)%1f%(creatordate:iso-strict)%1f%(contents:subject)",
+                BACKUP_REFS,
+            ])
+            .stdout()
+            .await?;
 
-        if has_error {
-            return None;
+        Ok(output
+            .lines()
+            .filter_map(|line| {
+                let [name, commit, base, created_at, message] = line.split('\u{1f}').collect::<Vec<_>>()[..] else {
+                    return None;
+                };
+                Some(BackupInfo {
+                    name: name.to_string(),
+                    commit: commit.to_string(),
+                    base: base.to_string(),
+                    created_at: created_at.to_string(),
+                    message: message.to_string(),
+                })
+            })
+            .collect())
+    }
+
+    /// Re-applies a backup's changes to the current checkout, as unstaged changes.
+    /// Like `git cherry-pick`, conflicting files are left with conflict markers; the
+    /// backup itself stays for another try.
+    pub async fn restore_backup(work_dir: &str, name: &str) -> Result<(), GitError> {
+        let backup = Self::backup_ref(work_dir, name).await?;
+        GitCommand::new(work_dir)
+            .args(["cherry-pick", "--no-commit", "--end-of-options", &backup])
+            .envs(BACKUP_IDENTITY)
+            .no_timeout()
+            .output()
+            .await?;
+        // cherry-pick stages what it applied; leave it as plain working tree changes.
+        GitCommand::new(work_dir).args(["reset", "--quiet"]).output().await?;
+        Ok(())
+    }
+
+    pub async fn delete_backup(work_dir: &str, name: &str) -> Result<(), GitError> {
+        let backup = Self::backup_ref(work_dir, name).await?;
+        GitCommand::new(work_dir).args(["update-ref", "-d", &backup]).output().await?;
+        Ok(())
+    }
+
+    /// `name` if it is one of the backups, so callers cannot point git at other refs.
+    async fn backup_ref(work_dir: &str, name: &str) -> Result<String, GitError> {
+        Self::list_backups(work_dir)
+            .await?
+            .into_iter()
+            .find(|b| b.name == name)
+            .map(|b| b.name)
+            .ok_or_else(|| GitError::from_failure(None, format!("Backup {} not found", name)))
+    }
+
+    /// Removes untracked files (and ignored ones for `UntrackedAndIgnored`) except
+    /// those matching `policy.preserve`. Nested repositories such as submodules stay.
+    pub async fn clean(work_dir: &str, policy: &CleanPolicy) -> Result<(), GitError> {
+        let Some(args) = clean_args(policy) else {
+            return Ok(());
+        };
+        GitCommand::new(work_dir).args(args).arg("-f").no_timeout().output().await?;
+        Ok(())
+    }
+
+    /// What `clean` would remove under `policy`, split into untracked and ignored
+    /// paths. Directories are listed once with a trailing `/`.
+    pub async fn clean_preview(work_dir: &str, policy: &CleanPolicy) -> Result<(Vec<String>, Vec<String>), GitError> {
+        let removed = Self::clean_dry_run(work_dir, policy).await?;
+        if policy.mode != CleanMode::UntrackedAndIgnored {
+            return Ok((removed, Vec::new()));
         }
+        let untracked_only = CleanPolicy { mode: CleanMode::Untracked, preserve: policy.preserve.clone() };
+        let untracked = Self::clean_dry_run(work_dir, &untracked_only).await?;
+        let ignored = removed.into_iter().filter(|path| !untracked.contains(path)).collect();
+        Ok((untracked, ignored))
+    }
 
-        let result = String::from_utf8_lossy(&output.stdout);
-        
-        let mut seen_versions = std::collections::HashSet::new();
-        let mut tags: Vec<(String, String)> = Vec::new();
-        
-        let has_dev = Self::has_remote_branch(work_dir, "dev").await;
-        let has_qa = Self::has_remote_branch(work_dir, "qa").await;
-        
-        if has_dev {
-            tags.push(("BRANCH:dev".to_string(), "dev-latest".to_string()));
-            seen_versions.insert("dev-latest".to_string());
+    async fn clean_dry_run(work_dir: &str, policy: &CleanPolicy) -> Result<Vec<String>, GitError> {
+        let Some(args) = clean_args(policy) else {
+            return Ok(Vec::new());
+        };
+        let output = GitCommand::new(work_dir)
+            .args(["-c", "core.quotePath=false"])
+            .args(args)
+            .arg("-n")
+            .stdout()
+            .await?;
+        Ok(output.lines().filter_map(|line| line.strip_prefix("Would remove ")).map(str::to_string).collect())
+    }
+
+    pub async fn check_out(work_dir: &str, branch: &str, discard_all: bool) -> Result<(), GitError> {
+        if discard_all {
+            Self::reset_hard(work_dir).await?;
         }
-        if has_qa {
-            tags.push(("BRANCH:qa".to_string(), "qa-latest".to_string()));
-            seen_versions.insert("qa-latest".to_string());
+        GitCommand::new(work_dir).args(["checkout", branch]).envs(SKIP_LFS_SMUDGE).no_timeout().output().await?;
+        Ok(())
+    }
+
+    pub async fn fetch(work_dir: &str, options: &CloneOptions) -> Result<(), GitError> {
+        GitCommand::new(work_dir)
+            .arg("fetch")
+            .ssh_key(options.ssh_key_path.as_deref())
+            .timeout(NETWORK_TIMEOUT)
+            .output()
+            .await?;
+        Ok(())
+    }
+
+    pub async fn fetch_tags(work_dir: &str) -> Result<(), GitError> {
+        GitCommand::new(work_dir).args(["fetch", "--tags"]).timeout(NETWORK_TIMEOUT).output().await?;
+        Ok(())
+    }
+
+    /// Fetches tags while keeping a shallow clone shallow: without `--depth`
+    /// every newly fetched tag would pull in its full history.
+    pub async fn fetch_tags_with(work_dir: &str, options: &CloneOptions) -> Result<(), GitError> {
+        let mut command = GitCommand::new(work_dir)
+            .args(["fetch", "--tags"])
+            .ssh_key(options.ssh_key_path.as_deref())
+            .timeout(NETWORK_TIMEOUT);
+        if let Some(depth) = options.depth {
+            command = command.arg(format!("--depth={}", depth));
         }
-        
-        let tag_entries: Vec<(String, String)> = result
-            .lines()
-            .map(|line| line.trim().to_string())
-            .filter(|t| !t.is_empty())
-            .filter(|tag| {
-                let lower = tag.to_lowercase();
-                lower.contains("dev") || lower.contains("qa")
-            })
-            .map(|tag| {
-                let display = Self::format_tag_display(&tag);
-                (tag, display)
-            })
-            .filter(|(_, display)| seen_versions.insert(display.clone()))
-            .take(limit.saturating_sub(tags.len()))
-            .collect();
-        
-        tags.extend(tag_entries);
-        
-        Some(tags)
+        if Self::has_submodules(work_dir) {
+            // Also fetches submodule commits referenced by the new superproject commits,
+            // so a later switch does not have to reach the network for each submodule.
+            command = command.arg("--recurse-submodules=on-demand");
+        }
+        command.output().await?;
+        Ok(())
     }
 
-    fn format_tag_display(tag: &str) -> String {
-        let lower = tag.to_lowercase();
-        
-        let prefix = if lower.contains("dev") {
-            "dev"
-        } else if lower.contains("qa") {
-            "qa"
-        } else {
-            return tag.to_string();
+    /// Deepens a shallow clone by `depth` commits, or fetches the full history when `None`.
+    pub async fn deepen(work_dir: &str, depth: Option<u32>, options: &CloneOptions) -> Result<(), GitError> {
+        let deepen = match depth {
+            Some(depth) => format!("--deepen={}", depth),
+            None => "--unshallow".to_string(),
         };
+        GitCommand::new(work_dir)
+            .args(["fetch".to_string(), deepen])
+            .ssh_key(options.ssh_key_path.as_deref())
+            .timeout(NETWORK_TIMEOUT)
+            .output()
+            .await?;
+        Ok(())
+    }
 
-        match Self::extract_version(tag) {
-            Some(v) => format!("{}-{}", prefix, v),
-            None => tag.to_string(),
+    /// Restricts the working tree to the given cone-mode directories, or restores
+    /// the full tree when `paths` is empty.
+    pub async fn sparse_checkout_set(work_dir: &str, paths: &[String]) -> Result<(), GitError> {
+        if paths.is_empty() {
+            return Self::sparse_checkout_disable(work_dir).await;
         }
+        GitCommand::new(work_dir)
+            .args(["sparse-checkout", "set", "--cone"])
+            .args(paths.iter().map(String::as_str))
+            .envs(SKIP_LFS_SMUDGE)
+            .no_timeout()
+            .output()
+            .await?;
+        Ok(())
+    }
+
+    pub async fn sparse_checkout_reapply(work_dir: &str) -> Result<(), GitError> {
+        GitCommand::new(work_dir).args(["sparse-checkout", "reapply"]).envs(SKIP_LFS_SMUDGE).no_timeout().output().await?;
+        Ok(())
     }
 
-    fn extract_version(tag: &str) -> Option<String> {
-        let mut version_parts: Vec<char> = Vec::new();
-        let mut in_version = false;
-        let mut dot_count = 0;
+    pub async fn sparse_checkout_disable(work_dir: &str) -> Result<(), GitError> {
+        GitCommand::new(work_dir).args(["sparse-checkout", "disable"]).envs(SKIP_LFS_SMUDGE).no_timeout().output().await?;
+        Ok(())
+    }
 
-        for ch in tag.chars() {
-            if ch.is_ascii_digit() {
-                in_version = true;
-                version_parts.push(ch);
-            } else if ch == '.' && in_version && dot_count < 2 {
-                version_parts.push(ch);
-                dot_count += 1;
-            } else if in_version {
-                break;
-            }
+    /// Tags newest first, with their details from a single `for-each-ref`. Version
+    /// ordering happens after channel matching, so it starts from creator date order here.
+    pub async fn get_tags(work_dir: &str, sort: TagSortMode, limit: usize) -> Result<Vec<TagRef>, GitError> {
+        let sort = match sort {
+            TagSortMode::TaggerDate => "--sort=-taggerdate",
+            TagSortMode::Version | TagSortMode::CreatorDate => "--sort=-creatordate",
+        };
+        // `*` fields are those of the commit an annotated tag points at; they are
+        // empty for lightweight tags, which point at the commit directly.
+        let format = [
+            "%(refname:lstrip=2)",
+            "%(objecttype)",
+            "%(objectname)",
+            "%(*objectname)",
+            "%(committerdate:iso-strict)",
+            "%(*committerdate:iso-strict)",
+            "%(taggerdate:iso-strict)",
+            "%(taggername) %(taggeremail)",
+            "%(contents:subject)%0a%0a%(contents:body)",
+        ]
+        .join("%1f")
+            + "%1e";
+        let result = GitCommand::new(work_dir)
+            .args(["for-each-ref", sort, &format!("--format={}", format), "refs/tags"])
+            .stdout()
+            .await?;
+
+        let non_empty = |field: &str| {
+            let field = field.trim();
+            if field.is_empty() { None } else { Some(field.to_string()) }
+        };
+        let tags: Vec<TagRef> = result
+            .split('\u{1e}')
+            .filter_map(|record| {
+                let fields: Vec<&str> = record.trim_start_matches('\n').split('\u{1f}').collect();
+                let [name, object_type, object, peeled, date, peeled_date, tag_date, tagger, message] = fields[..] else {
+                    return None;
+                };
+                let annotated = object_type == "tag";
+                Some(TagRef {
+                    name: non_empty(name)?,
+                    details: TagDetails {
+                        commit: if annotated { peeled } else { object }.to_string(),
+                        commit_date: non_empty(if annotated { peeled_date } else { date }),
+                        tag_date: non_empty(tag_date),
+                        tagger: if annotated { non_empty(tagger) } else { None },
+                        message: if annotated { non_empty(message) } else { None },
+                    },
+                })
+            })
+            .take(limit)
+            .collect();
+        Ok(tags)
+    }
+
+    /// Commits reachable from `to` but not from `from`, newest first, with a diffstat
+    /// each. Returns whether the list was cut at `limit`.
+    pub async fn log_range(work_dir: &str, from: &str, to: &str, limit: usize) -> Result<(Vec<ChangelogCommit>, bool), GitError> {
+        let range = format!("{}..{}", from, to);
+        Self::log_commits(work_dir, &["--end-of-options", &range], limit).await
+    }
+
+    /// Commits in HEAD that no remote branch or tag contains, i.e. work that only
+    /// exists in this checkout.
+    pub async fn local_commits(work_dir: &str, limit: usize) -> Result<(Vec<ChangelogCommit>, bool), GitError> {
+        Self::log_commits(work_dir, &["HEAD", "--not", "--remotes", "--tags"], limit).await
+    }
+
+    async fn log_commits(work_dir: &str, revisions: &[&str], limit: usize) -> Result<(Vec<ChangelogCommit>, bool), GitError> {
+        let output = GitCommand::new(work_dir)
+            .args([
+                "log",
+                "--format=%x1e%H%x1f%P%x1f%an%x1f%aI%x1f%s",
+                "--shortstat",
+                &format!("--max-count={}", limit + 1),
+            ])
+            .args(revisions.iter().copied())
+            .stdout()
+            .await?;
+
+        let mut parents_of = HashMap::new();
+        let mut commits: Vec<ChangelogCommit> = Vec::new();
+        for record in output.split('\u{1e}').filter(|r| !r.trim().is_empty()) {
+            let (header, stat) = record.split_once('\n').unwrap_or((record, ""));
+            let [hash, parents, author, date, subject] = header.split('\u{1f}').collect::<Vec<_>>()[..] else {
+                continue;
+            };
+            let parents: Vec<String> = parents.split_whitespace().map(str::to_string).collect();
+            let (files_changed, insertions, deletions) = parse_shortstat(stat);
+            commits.push(ChangelogCommit {
+                hash: hash.to_string(),
+                author: author.to_string(),
+                date: date.to_string(),
+                subject: subject.to_string(),
+                is_merge: parents.len() > 1,
+                merged_by: None,
+                files_changed,
+                insertions,
+                deletions,
+            });
+            parents_of.insert(hash.to_string(), parents);
         }
 
-        if version_parts.is_empty() {
-            None
-        } else {
-            let version: String = version_parts.into_iter().collect();
-            let version = version.trim_end_matches('.').to_string();
-            if version.is_empty() { None } else { Some(version) }
+        let truncated = commits.len() > limit;
+        commits.truncate(limit);
+        assign_merges(&mut commits, &parents_of);
+        Ok((commits, truncated))
+    }
+
+    pub async fn checkout_tag(work_dir: &str, tag: &str) -> Result<(), GitError> {
+        GitCommand::new(work_dir)
+            .args(["checkout", &format!("tags/{}", tag)])
+            .envs(SKIP_LFS_SMUDGE)
+            .no_timeout()
+            .output()
+            .await?;
+        Ok(())
+    }
+}
+
+/// The `git clean` arguments for `policy` without `-f`/`-n`, or `None` when it removes nothing.
+fn clean_args(policy: &CleanPolicy) -> Option<Vec<String>> {
+    let mut args = vec!["clean".to_string(), "-d".to_string()];
+    match policy.mode {
+        CleanMode::None => return None,
+        CleanMode::Untracked => {}
+        // `--exclude` patterns still apply with `-x`, so the preserve list holds either way.
+        CleanMode::UntrackedAndIgnored => args.push("-x".to_string()),
+    }
+    for pattern in policy.preserve.iter().map(|p| p.trim()).filter(|p| !p.is_empty()) {
+        args.push(format!("--exclude={}", pattern));
+    }
+    Some(args)
+}
+
+/// `" 3 files changed, 10 insertions(+), 2 deletions(-)"` as its three numbers.
+fn parse_shortstat(stat: &str) -> (u32, u32, u32) {
+    let (mut files, mut insertions, mut deletions) = (0, 0, 0);
+    for part in stat.trim().split(", ") {
+        let Some((count, what)) = part.split_once(' ') else {
+            continue;
+        };
+        let count = count.parse().unwrap_or(0);
+        if what.starts_with("file") {
+            files = count;
+        } else if what.starts_with("insertion") {
+            insertions = count;
+        } else if what.starts_with("deletion") {
+            deletions = count;
         }
     }
+    (files, insertions, deletions)
+}
 
-    pub async fn checkout_tag(work_dir: &str, tag: &str, discard_all: bool) -> bool {
-        if discard_all {
-            Self::reset_hard(work_dir).await;
+/// Walks the first-parent line from the newest commit and files every commit that a
+/// merge on it brought in under that merge.
+fn assign_merges(commits: &mut [ChangelogCommit], parents_of: &HashMap<String, Vec<String>>) {
+    let Some(newest) = commits.first().map(|c| c.hash.clone()) else {
+        return;
+    };
+
+    let mut mainline = HashSet::new();
+    let mut current = Some(newest);
+    while let Some(hash) = current.take() {
+        let Some(parents) = parents_of.get(&hash) else {
+            break;
+        };
+        current = parents.first().cloned();
+        mainline.insert(hash);
+    }
+
+    let mut merged_by: HashMap<String, String> = HashMap::new();
+    // Oldest merge first, so a branch merged twice keeps its early commits under the first merge.
+    for commit in commits.iter().rev().filter(|c| c.is_merge && mainline.contains(&c.hash)) {
+        let mut pending: Vec<String> = parents_of[&commit.hash][1..].to_vec();
+        while let Some(hash) = pending.pop() {
+            if mainline.contains(&hash) || merged_by.contains_key(&hash) || !parents_of.contains_key(&hash) {
+                continue;
+            }
+            pending.extend(parents_of[&hash].iter().cloned());
+            merged_by.insert(hash, commit.hash.clone());
         }
-        let (has_error, _) = Self::run_command(work_dir, &format!("checkout tags/{}", tag)).await;
-        !has_error
     }
-}
\ No newline at end of file
+
+    for commit in commits.iter_mut() {
+        commit.merged_by = merged_by.get(&commit.hash).cloned();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_shortstat() {
+        assert_eq!(parse_shortstat(" 3 files changed, 10 insertions(+), 2 deletions(-)"), (3, 10, 2));
+        assert_eq!(parse_shortstat(" 1 file changed, 5 insertions(+)"), (1, 5, 0));
+        assert_eq!(parse_shortstat(" 2 files changed, 1 deletion(-)"), (2, 0, 1));
+        assert_eq!(parse_shortstat(""), (0, 0, 0));
+    }
+
+    /// `A..M2` of this history, newest first, with `feature` and `topic` merged `--no-ff`:
+    ///
+    /// ```text
+    /// A - B ------- M1 - C ----- M2
+    ///  \           /      \     /
+    ///   F1 ---- F2          G1
+    /// ```
+    #[test]
+    fn files_merged_commits_under_their_merge() {
+        let history = [
+            ("M2", vec!["C", "G1"]),
+            ("G1", vec!["C"]),
+            ("C", vec!["M1"]),
+            ("M1", vec!["B", "F2"]),
+            ("F2", vec!["F1"]),
+            ("F1", vec!["A"]),
+            ("B", vec!["A"]),
+        ];
+        let mut commits: Vec<ChangelogCommit> = history
+            .iter()
+            .map(|(hash, parents)| ChangelogCommit {
+                hash: hash.to_string(),
+                author: String::new(),
+                date: String::new(),
+                subject: String::new(),
+                is_merge: parents.len() > 1,
+                merged_by: None,
+                files_changed: 0,
+                insertions: 0,
+                deletions: 0,
+            })
+            .collect();
+        let parents_of: HashMap<String, Vec<String>> = history
+            .iter()
+            .map(|(hash, parents)| (hash.to_string(), parents.iter().map(|p| p.to_string()).collect()))
+            .collect();
+
+        assign_merges(&mut commits, &parents_of);
+
+        let merged_by: Vec<(&str, Option<&str>)> =
+            commits.iter().map(|c| (c.hash.as_str(), c.merged_by.as_deref())).collect();
+        assert_eq!(
+            merged_by,
+            [
+                ("M2", None),
+                ("G1", Some("M2")),
+                ("C", None),
+                ("M1", None),
+                ("F2", Some("M1")),
+                ("F1", Some("M1")),
+                ("B", None),
+            ]
+        );
+    }
+}
@@ -0,0 +1,284 @@
+use std::fmt;
+use std::process::{Output, Stdio};
+use std::time::Duration;
+use tokio::process::{Child, Command};
+use crate::modules::credentials;
+use crate::modules::error::GitError;
+
+#[cfg(windows)]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+/// Limit for commands that only read the local repository. Commands that write
+/// the working tree run with `no_timeout`: killing a checkout halfway leaves
+/// `index.lock` behind and a tree that is neither version.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Limit for commands that talk to the remote without reporting progress.
+pub const NETWORK_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// One git invocation. Arguments are passed to git as-is, so paths, branches
+/// and tags may contain spaces. Terminal prompts are always disabled: git runs
+/// without a terminal, and credentials come from the app's askpass helper instead.
+pub struct GitCommand {
+    work_dir: String,
+    args: Vec<String>,
+    envs: Vec<(String, String)>,
+    timeout: Option<Duration>,
+}
+
+impl GitCommand {
+    pub fn new(work_dir: &str) -> Self {
+        GitCommand {
+            work_dir: work_dir.to_string(),
+            args: Vec::new(),
+            envs: Vec::new(),
+            timeout: Some(DEFAULT_TIMEOUT),
+        }
+    }
+
+    pub fn arg(mut self, arg: impl Into<String>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn env(mut self, key: &str, value: &str) -> Self {
+        self.envs.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn envs(mut self, envs: &[(&str, &str)]) -> Self {
+        for (key, value) in envs {
+            self = self.env(key, value);
+        }
+        self
+    }
+
+    /// Makes ssh use this private key, and only this one, for the remote.
+    pub fn ssh_key(self, key_path: Option<&str>) -> Self {
+        match key_path.filter(|p| !p.is_empty()) {
+            Some(path) => {
+                // GIT_SSH_COMMAND goes through a shell, also git's own sh on Windows.
+                let path = path.replace('\\', "/").replace('\'', "'\\''");
+                let command = format!("ssh -i '{}' -o IdentitiesOnly=yes -o BatchMode=yes -o StrictHostKeyChecking=accept-new", path);
+                self.env("GIT_SSH_COMMAND", &command)
+            }
+            None => self,
+        }
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// For long transfers that report progress and can be cancelled instead, and
+    /// for commands that write the working tree, which must not be killed midway.
+    pub fn no_timeout(mut self) -> Self {
+        self.timeout = None;
+        self
+    }
+
+    pub fn work_dir(&self) -> &str {
+        &self.work_dir
+    }
+
+    pub fn time_limit(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    fn command(&self) -> Command {
+        let mut command = Command::new("git");
+        command
+            .args(&self.args)
+            .current_dir(&self.work_dir)
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .envs(credentials::git_env())
+            .envs(self.envs.iter().map(|(key, value)| (key, value)))
+            .kill_on_drop(true);
+
+        #[cfg(windows)]
+        command.creation_flags(CREATE_NO_WINDOW);
+
+        command
+    }
+
+    /// Runs git to completion and returns its output, or the classified failure
+    /// when it could not be started, exited non-zero or ran past the timeout.
+    pub async fn output(self) -> Result<Output, GitError> {
+        let mut command = self.command();
+
+        let output = match self.timeout {
+            Some(limit) => match tokio::time::timeout(limit, command.output()).await {
+                Ok(output) => output,
+                Err(_) => {
+                    // Dropping the output future kills git thanks to kill_on_drop.
+                    println!("[GitCommand] `{}` timed out after {}s", self, limit.as_secs());
+                    return Err(GitError::TimedOut { seconds: limit.as_secs() });
+                }
+            },
+            None => command.output().await,
+        };
+
+        let output = output.map_err(|e| GitError::SpawnFailed { message: e.to_string() })?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            println!("[GitCommand] `{}` failed: {}", self, stderr.trim());
+            return Err(GitError::from_failure(output.status.code(), stderr));
+        }
+
+        Ok(output)
+    }
+
+    /// Runs git to completion and returns its stdout.
+    pub async fn stdout(self) -> Result<String, GitError> {
+        let output = self.output().await?;
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    /// Starts git with stdout and stderr piped for progress parsing. The caller is responsible
+    /// for enforcing `time_limit`; git and everything it started are killed when the
+    /// returned process is dropped.
+    pub fn spawn(&self) -> Result<GitProcess, GitError> {
+        let mut command = self.command();
+        command.stdout(Stdio::piped()).stderr(Stdio::piped());
+        // Its own process group, so a kill also reaches remote helpers, index-pack
+        // and submodule clones instead of leaving them writing to the directory.
+        #[cfg(unix)]
+        command.process_group(0);
+
+        let child = command.spawn().map_err(|e| GitError::SpawnFailed { message: e.to_string() })?;
+
+        #[cfg(windows)]
+        let job = match job::Job::new().and_then(|job| job.assign(&child).map(|_| job)) {
+            Ok(job) => Some(job),
+            Err(e) => {
+                println!("[GitCommand] Could not put `{}` in a job object: {}", self, e);
+                None
+            }
+        };
+
+        Ok(GitProcess {
+            child,
+            #[cfg(windows)]
+            job,
+        })
+    }
+}
+
+/// A git process started by `GitCommand::spawn`, together with the processes it starts.
+pub struct GitProcess {
+    pub child: Child,
+    #[cfg(windows)]
+    job: Option<job::Job>,
+}
+
+impl GitProcess {
+    /// Kills git and every process it started, then waits for git to exit.
+    pub async fn kill(&mut self) -> std::io::Result<()> {
+        self.kill_tree();
+        self.child.kill().await
+    }
+
+    fn kill_tree(&self) {
+        // `id` is None once git has been waited for, so the group id cannot have been reused.
+        #[cfg(unix)]
+        if let Some(pid) = self.child.id() {
+            unsafe {
+                libc::killpg(pid as libc::pid_t, libc::SIGKILL);
+            }
+        }
+        #[cfg(windows)]
+        if let Some(job) = &self.job {
+            job.terminate();
+        }
+    }
+}
+
+impl Drop for GitProcess {
+    fn drop(&mut self) {
+        self.kill_tree();
+    }
+}
+
+/// Windows has no process groups; a job object created to kill its processes
+/// when closed does the same.
+#[cfg(windows)]
+mod job {
+    use std::io;
+    use tokio::process::Child;
+    use windows_sys::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows_sys::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectExtendedLimitInformation, SetInformationJobObject,
+        TerminateJobObject, JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+    };
+
+    pub struct Job(HANDLE);
+
+    // The handle is only passed to kernel calls, which may come from any thread.
+    unsafe impl Send for Job {}
+    unsafe impl Sync for Job {}
+
+    impl Job {
+        pub fn new() -> io::Result<Job> {
+            unsafe {
+                let handle = CreateJobObjectW(std::ptr::null(), std::ptr::null());
+                if handle.is_null() {
+                    return Err(io::Error::last_os_error());
+                }
+                let job = Job(handle);
+
+                let mut limits: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = std::mem::zeroed();
+                limits.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+                let set = SetInformationJobObject(
+                    job.0,
+                    JobObjectExtendedLimitInformation,
+                    &limits as *const _ as *const std::ffi::c_void,
+                    std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+                );
+                if set == 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(job)
+            }
+        }
+
+        /// Adds `child` to the job; processes it starts from then on join as well.
+        pub fn assign(&self, child: &Child) -> io::Result<()> {
+            let process = child.raw_handle().ok_or_else(|| io::Error::other("git already exited"))?;
+            if unsafe { AssignProcessToJobObject(self.0, process as HANDLE) } == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        }
+
+        pub fn terminate(&self) {
+            unsafe {
+                TerminateJobObject(self.0, 1);
+            }
+        }
+    }
+
+    impl Drop for Job {
+        fn drop(&mut self) {
+            unsafe {
+                CloseHandle(self.0);
+            }
+        }
+    }
+}
+
+impl fmt::Display for GitCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "git {}", self.args.join(" "))
+    }
+}
@@ -0,0 +1,796 @@
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::num::NonZeroU32;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use async_trait::async_trait;
+use gix::bstr::BStr;
+use gix::filter::plumbing::pipeline::convert::ToGitOutcome;
+use gix::index::entry::{Flags, Mode, Stat};
+use gix::progress::tree::Root;
+use gix::progress::Discard;
+use gix::refs::transaction::{Change, LogChange, PreviousValue, RefEdit};
+use gix::refs::{FullName, Target};
+use gix::remote::fetch::{Shallow, Tags};
+use gix::remote::Direction;
+use tokio::sync::{mpsc, watch};
+use crate::modules::backend::GitBackend;
+use crate::modules::credentials::{self, HostCredential};
+use crate::modules::error::GitError;
+use crate::modules::git::{CloneProgress, Git};
+use crate::modules::types::{ChangelogCommit, CleanMode, CleanPolicy, CloneOptions, GitBackendKind, HeadInfo, HeadRef, TagDetails, TagRef, TagSortMode};
+
+/// How often the gix progress tree is sampled while cloning.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Pure Rust implementation on top of gix. Submodules, LFS content, sparse checkout,
+/// backups, clean policies and pin checks still need the git executable; they run
+/// with it when it is installed.
+pub struct GixBackend;
+
+#[async_trait]
+impl GitBackend for GixBackend {
+    fn kind(&self) -> GitBackendKind {
+        GitBackendKind::Gix
+    }
+
+    async fn clone_with_progress(
+        &self,
+        target_dir: &str,
+        remote_url: &str,
+        options: &CloneOptions,
+        progress_tx: mpsc::Sender<CloneProgress>,
+        cancel_rx: watch::Receiver<bool>,
+    ) -> Result<(), GitError> {
+        if !options.sparse_paths.is_empty() {
+            return Err(GitError::Unsupported { operation: "sparse checkout".to_string() });
+        }
+        if options.filter_blobs {
+            return Err(GitError::Unsupported { operation: "partial clone (--filter=blob:none)".to_string() });
+        }
+        reject_ssh_key(options)?;
+
+        let interrupt = Arc::new(AtomicBool::new(false));
+        let cancel_task = interrupt_on_cancel(cancel_rx.clone(), interrupt.clone());
+
+        let root = Root::new();
+        let done = Arc::new(AtomicBool::new(false));
+        let progress_task = tokio::spawn(forward_progress(root.clone(), progress_tx.clone(), done.clone()));
+
+        let target = target_dir.to_string();
+        let url = remote_url.to_string();
+        let clone_options = options.clone();
+        let should_interrupt = interrupt.clone();
+        let result = run_blocking(move || {
+            let mut prepare = gix::prepare_clone(url.as_str(), &target).map_err(gix_error)?;
+            if let Some(depth) = clone_options.depth.and_then(NonZeroU32::new) {
+                prepare = prepare.with_shallow(Shallow::DepthAtRemote(depth));
+            }
+            if let Some(branch) = clone_options.branch.as_deref().filter(|b| !b.is_empty()) {
+                prepare = prepare.with_ref_name(Some(branch)).map_err(gix_error)?;
+            }
+            if let Some(credential) = vault_credential(&url) {
+                prepare = prepare.configure_connection(move |connection| {
+                    connection.set_credentials(answer_from_vault(credential.clone()));
+                    Ok(())
+                });
+            }
+
+            let (mut checkout, _) = prepare
+                .fetch_then_checkout(root.add_child("fetch"), &should_interrupt)
+                .map_err(gix_error)?;
+            checkout
+                .main_worktree(root.add_child("checkout"), &should_interrupt)
+                .map_err(gix_error)?;
+            Ok(())
+        }).await;
+
+        done.store(true, Ordering::Relaxed);
+        progress_task.await.ok();
+        cancel_task.abort();
+
+        if interrupt.load(Ordering::Relaxed) {
+            return Err(GitError::Cancelled);
+        }
+        result?;
+
+        if (Git::has_submodules(target_dir) || Git::uses_lfs(target_dir)) && Git::is_git_in_path().await {
+            if Git::has_submodules(target_dir) {
+                Git::update_submodules(target_dir, options, progress_tx.clone(), cancel_rx.clone()).await?;
+            }
+            if Git::uses_lfs(target_dir) {
+                Git::lfs_fetch_and_checkout(target_dir, options, progress_tx, cancel_rx).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// gix only clones into an empty directory and cannot pick up a partial one.
+    fn supports_resume(&self) -> bool {
+        false
+    }
+
+    async fn resume_clone(
+        &self,
+        _target_dir: &str,
+        _remote_url: &str,
+        _options: &CloneOptions,
+        _progress_tx: mpsc::Sender<CloneProgress>,
+        _cancel_rx: watch::Receiver<bool>,
+    ) -> Result<(), GitError> {
+        Err(GitError::Unsupported { operation: "resuming an interrupted clone".to_string() })
+    }
+
+    async fn fetch(&self, work_dir: &str, options: &CloneOptions) -> Result<(), GitError> {
+        reject_ssh_key(options)?;
+        let work_dir = work_dir.to_string();
+        run_blocking(move || fetch_origin(&work_dir, Tags::Included, None)).await
+    }
+
+    async fn fetch_tags(&self, work_dir: &str, options: &CloneOptions) -> Result<(), GitError> {
+        reject_ssh_key(options)?;
+        let work_dir = work_dir.to_string();
+        let depth = options.depth;
+        run_blocking(move || fetch_origin(&work_dir, Tags::All, depth)).await
+    }
+
+    /// Annotated tags sort by their tagger date. For creator date, lightweight tags
+    /// fall back to their commit's date like `--sort=-creatordate`; for tagger date
+    /// they come last.
+    async fn list_tags(&self, work_dir: &str, sort: TagSortMode) -> Result<Vec<TagRef>, GitError> {
+        let work_dir = work_dir.to_string();
+        run_blocking(move || {
+            let repo = gix::open(&work_dir).map_err(gix_error)?;
+            let references = repo.references().map_err(gix_error)?;
+
+            let mut tags: Vec<(i64, TagRef)> = Vec::new();
+            for reference in references.tags().map_err(gix_error)? {
+                let mut reference = reference.map_err(gix_error)?;
+                let name = reference.name().shorten().to_string();
+                let annotated = reference
+                    .target()
+                    .try_id()
+                    .and_then(|id| repo.find_object(id.to_owned()).ok())
+                    .and_then(|object| object.try_into_tag().ok());
+                let (tag_time, tagger, message) = match annotated.as_ref().and_then(|tag| tag.decode().ok()) {
+                    Some(decoded) => (
+                        decoded.tagger.and_then(|tagger| tagger.time().ok()),
+                        decoded.tagger.map(|tagger| format!("{} <{}>", tagger.name, tagger.email)),
+                        Some(decoded.message.to_string().trim().to_string()).filter(|m| !m.is_empty()),
+                    ),
+                    None => (None, None, None),
+                };
+                let commit = reference.peel_to_commit().ok();
+                let commit_time = commit.as_ref().and_then(|commit| commit.time().ok());
+
+                let time = match (sort, tag_time) {
+                    (_, Some(time)) => time.seconds,
+                    (TagSortMode::TaggerDate, None) => i64::MIN,
+                    (_, None) => commit_time.map(|time| time.seconds).unwrap_or(0),
+                };
+                tags.push((time, TagRef {
+                    name,
+                    details: TagDetails {
+                        commit: commit.map(|commit| commit.id.to_string()).unwrap_or_default(),
+                        commit_date: commit_time.and_then(rfc3339),
+                        tag_date: tag_time.and_then(rfc3339),
+                        tagger,
+                        message,
+                    },
+                }));
+            }
+
+            tags.sort_by(|a, b| b.0.cmp(&a.0));
+            Ok(tags.into_iter().map(|(_, tag)| tag).collect())
+        }).await
+    }
+
+    async fn remote_branches(&self, work_dir: &str) -> Result<Vec<String>, GitError> {
+        let work_dir = work_dir.to_string();
+        run_blocking(move || {
+            let repo = gix::open(&work_dir).map_err(gix_error)?;
+            let references = repo.references().map_err(gix_error)?;
+
+            let mut branches = Vec::new();
+            for reference in references.remote_branches().map_err(gix_error)? {
+                let reference = reference.map_err(gix_error)?;
+                branches.push(reference.name().shorten().to_string());
+            }
+            Ok(branches)
+        }).await
+    }
+
+    async fn current_branch(&self, work_dir: &str) -> Result<String, GitError> {
+        let work_dir = work_dir.to_string();
+        run_blocking(move || {
+            let repo = gix::open(&work_dir).map_err(gix_error)?;
+            Ok(match repo.head_name().map_err(gix_error)? {
+                Some(name) => name.shorten().to_string(),
+                None => "HEAD".to_string(),
+            })
+        }).await
+    }
+
+    /// A detached HEAD without a tag is described by its abbreviated commit id,
+    /// where `git describe` would also count the commits since the last tag.
+    async fn head(&self, work_dir: &str, preferred_tags: &[String]) -> Result<HeadInfo, GitError> {
+        let work_dir = work_dir.to_string();
+        let preferred_tags = preferred_tags.to_vec();
+        run_blocking(move || {
+            let repo = gix::open(&work_dir).map_err(gix_error)?;
+            let commit = repo.head_commit().map_err(gix_error)?;
+            let subject = commit.message().map_err(gix_error)?.summary().to_string();
+            let date = commit.time().ok().and_then(rfc3339).unwrap_or_default();
+
+            let reference = match repo.head_name().map_err(gix_error)? {
+                Some(name) => {
+                    let upstream = repo
+                        .branch_remote_tracking_ref_name(name.as_ref(), Direction::Fetch)
+                        .and_then(Result::ok)
+                        .map(|upstream| upstream.shorten().to_string());
+                    HeadRef::Branch { name: name.shorten().to_string(), upstream }
+                }
+                None => {
+                    let references = repo.references().map_err(gix_error)?;
+                    let mut tags = Vec::new();
+                    for reference in references.tags().map_err(gix_error)? {
+                        let mut reference = reference.map_err(gix_error)?;
+                        if reference.peel_to_commit().is_ok_and(|tagged| tagged.id == commit.id) {
+                            tags.push(reference.name().shorten().to_string());
+                        }
+                    }
+                    match tags.iter().find(|t| preferred_tags.contains(t)).or(tags.first()) {
+                        Some(name) => HeadRef::Tag { name: name.clone() },
+                        None => HeadRef::Detached { describe: commit.short_id().map_err(gix_error)?.to_string() },
+                    }
+                }
+            };
+
+            Ok(HeadInfo { reference, commit: commit.id.to_string(), subject, date })
+        }).await
+    }
+
+    /// Moves the local branch to `origin/<branch>`. Unlike `git pull` this does not
+    /// merge: local commits on that branch are dropped, which suits a version switcher.
+    async fn checkout_branch(&self, work_dir: &str, branch: &str, _options: &CloneOptions) -> Result<(), GitError> {
+        let work_dir = work_dir.to_string();
+        let branch = branch.to_string();
+        run_blocking(move || {
+            let repo = gix::open(&work_dir).map_err(gix_error)?;
+            let commit_id = repo
+                .find_reference(format!("refs/remotes/origin/{}", branch).as_str())
+                .map_err(gix_error)?
+                .peel_to_commit()
+                .map_err(gix_error)?
+                .id;
+
+            checkout_commit(&repo, commit_id)?;
+
+            let local = format!("refs/heads/{}", branch);
+            repo.reference(local.as_str(), commit_id, PreviousValue::Any, format!("repohub: checkout {}", branch))
+                .map_err(gix_error)?;
+            let local: FullName = local.as_str().try_into().map_err(gix_error)?;
+            set_head(&repo, Target::Symbolic(local), format!("checkout: moving to {}", branch))
+        }).await
+    }
+
+    async fn checkout_tag(&self, work_dir: &str, tag: &str) -> Result<(), GitError> {
+        let work_dir = work_dir.to_string();
+        let tag = tag.to_string();
+        run_blocking(move || {
+            let repo = gix::open(&work_dir).map_err(gix_error)?;
+            let commit_id = repo
+                .find_reference(format!("refs/tags/{}", tag).as_str())
+                .map_err(gix_error)?
+                .peel_to_commit()
+                .map_err(gix_error)?
+                .id;
+
+            checkout_commit(&repo, commit_id)?;
+            set_head(&repo, Target::Object(commit_id), format!("checkout: moving to tags/{}", tag))
+        }).await
+    }
+
+    async fn reset_hard(&self, work_dir: &str) -> Result<(), GitError> {
+        let work_dir = work_dir.to_string();
+        run_blocking(move || {
+            let repo = gix::open(&work_dir).map_err(gix_error)?;
+            let head_id = repo.head_commit().map_err(gix_error)?.id;
+            checkout_commit(&repo, head_id)
+        }).await
+    }
+
+    async fn reset_to(&self, work_dir: &str, commit: &str) -> Result<(), GitError> {
+        let work_dir = work_dir.to_string();
+        let commit = commit.to_string();
+        run_blocking(move || {
+            let repo = gix::open(&work_dir).map_err(gix_error)?;
+            let commit_id = gix::ObjectId::from_hex(commit.as_bytes()).map_err(gix_error)?;
+
+            checkout_commit(&repo, commit_id)?;
+
+            let message = format!("reset: moving to {}", commit);
+            match repo.head_name().map_err(gix_error)? {
+                Some(branch) => {
+                    repo.reference(branch, commit_id, PreviousValue::Any, message).map_err(gix_error)?;
+                    Ok(())
+                }
+                None => set_head(&repo, Target::Object(commit_id), message),
+            }
+        }).await
+    }
+
+    /// Compares the index with HEAD and with the files on disk like `git status`,
+    /// hashing a file through the configured filters only when its stat data changed.
+    async fn working_tree_changes(&self, work_dir: &str) -> Result<(Vec<String>, Vec<String>, Vec<String>), GitError> {
+        let work_dir = work_dir.to_string();
+        run_blocking(move || {
+            let repo = gix::open(&work_dir).map_err(gix_error)?;
+            let workdir = repo
+                .workdir()
+                .ok_or_else(|| gix_error("repository has no working tree"))?
+                .to_path_buf();
+            let (mut pipeline, index) = repo.filter_pipeline(None).map_err(gix_error)?;
+            let head_tree = repo.head_tree_id_or_empty().map_err(gix_error)?.detach();
+            let head_index = repo.index_from_tree(&head_tree).map_err(gix_error)?;
+
+            let in_head: HashMap<&BStr, (gix::ObjectId, Mode)> =
+                head_index.entries().iter().map(|entry| (entry.path(&head_index), (entry.id, entry.mode))).collect();
+            let mut in_index = HashSet::new();
+            let mut staged = Vec::new();
+            for entry in index.entries() {
+                let path = entry.path(&index);
+                // A conflicted path has one entry per stage.
+                if in_index.insert(path) && (entry.stage_raw() != 0 || in_head.get(path) != Some(&(entry.id, entry.mode))) {
+                    staged.push(path.to_string());
+                }
+            }
+            staged.extend(in_head.keys().filter(|path| !in_index.contains(*path)).map(|path| path.to_string()));
+            staged.sort();
+
+            let options = repo.stat_options().map_err(gix_error)?;
+            let capabilities = repo.filesystem_options().map_err(gix_error)?;
+            let mut modified: Vec<String> = Vec::new();
+            for entry in index.entries() {
+                if entry.flags.intersects(Flags::ASSUME_VALID | Flags::SKIP_WORKTREE) || entry.mode.is_submodule() {
+                    continue;
+                }
+                let path = entry.path(&index);
+                let changed = entry.stage_raw() != 0 || {
+                    let metadata = match gix::index::fs::Metadata::from_path_no_follow(&workdir.join(gix::path::from_bstr(path))) {
+                        Ok(metadata) => Some(metadata),
+                        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+                        Err(e) => return Err(gix_error(e)),
+                    };
+                    match metadata {
+                        None => true,
+                        Some(metadata) => {
+                            entry.mode.change_to_match_fs(&metadata, capabilities.symlink, capabilities.executable_bit).is_some()
+                                || content_changed(&mut pipeline, &index, &workdir, entry, path, &metadata, options)?
+                        }
+                    }
+                };
+                if changed && modified.last().map(String::as_bytes) != Some(&path[..]) {
+                    modified.push(path.to_string());
+                }
+            }
+
+            let tracked_dirs: HashSet<&BStr> = in_index
+                .iter()
+                .flat_map(|&path| path.iter().enumerate().filter(|(_, byte)| **byte == b'/').map(move |(at, _)| BStr::new(&path[..at])))
+                .collect();
+            let tracked = Tracked { files: &in_index, dirs: &tracked_dirs };
+            let mut excludes = repo
+                .excludes(&index, None, gix::worktree::stack::state::ignore::Source::WorktreeThenIdMappingIfNotSkipped)
+                .map_err(gix_error)?;
+            let mut untracked = Vec::new();
+            untracked_paths(&workdir, "", &tracked, &mut excludes, false, &mut untracked)?;
+            untracked.sort();
+
+            Ok((modified, staged, untracked))
+        }).await
+    }
+
+    async fn ahead_behind(&self, work_dir: &str, upstream: &str) -> Result<(u32, u32), GitError> {
+        let work_dir = work_dir.to_string();
+        let upstream = upstream.to_string();
+        run_blocking(move || {
+            let repo = gix::open(&work_dir).map_err(gix_error)?;
+            let head = repo.head_commit().map_err(gix_error)?.id;
+            let upstream = repo
+                .find_reference(upstream.as_str())
+                .map_err(gix_error)?
+                .peel_to_commit()
+                .map_err(gix_error)?
+                .id;
+            Ok((count_commits(&repo, head, [upstream])?, count_commits(&repo, upstream, [head])?))
+        }).await
+    }
+
+    async fn hidden_tracked_files(&self, work_dir: &str, sparse: bool) -> Result<Vec<String>, GitError> {
+        let work_dir = work_dir.to_string();
+        run_blocking(move || {
+            let repo = gix::open(&work_dir).map_err(gix_error)?;
+            let index = repo.index_or_empty().map_err(gix_error)?;
+            Ok(index
+                .entries()
+                .iter()
+                .filter(|entry| entry.flags.contains(Flags::ASSUME_VALID) || (entry.flags.contains(Flags::SKIP_WORKTREE) && !sparse))
+                .map(|entry| entry.path(&index).to_string())
+                .collect())
+        }).await
+    }
+
+    /// Lists commits without their diff stats or merge grouping, which `git log`
+    /// works out for the changelog.
+    async fn local_commits(&self, work_dir: &str, limit: usize) -> Result<(Vec<ChangelogCommit>, bool), GitError> {
+        let work_dir = work_dir.to_string();
+        run_blocking(move || {
+            let repo = gix::open(&work_dir).map_err(gix_error)?;
+            let head = repo.head_commit().map_err(gix_error)?.id;
+            let references = repo.references().map_err(gix_error)?;
+            let mut published = Vec::new();
+            for reference in references.remote_branches().map_err(gix_error)?.chain(references.tags().map_err(gix_error)?) {
+                if let Ok(commit) = reference.map_err(gix_error)?.peel_to_commit() {
+                    published.push(commit.id);
+                }
+            }
+
+            let walk = repo
+                .rev_walk([head])
+                .with_hidden(published)
+                .sorting(gix::revision::walk::Sorting::ByCommitTime(Default::default()))
+                .all()
+                .map_err(gix_error)?;
+            let mut commits = Vec::new();
+            for info in walk.take(limit + 1) {
+                let info = info.map_err(gix_error)?;
+                let commit = info.object().map_err(gix_error)?;
+                let author = commit.author().map_err(gix_error)?;
+                commits.push(ChangelogCommit {
+                    hash: info.id.to_string(),
+                    author: author.name.to_string(),
+                    date: author.time().ok().and_then(rfc3339).unwrap_or_default(),
+                    subject: commit.message().map_err(gix_error)?.summary().to_string(),
+                    is_merge: info.parent_ids.len() > 1,
+                    merged_by: None,
+                    files_changed: 0,
+                    insertions: 0,
+                    deletions: 0,
+                });
+            }
+
+            let truncated = commits.len() > limit;
+            commits.truncate(limit);
+            Ok((commits, truncated))
+        }).await
+    }
+
+    /// Cleaning runs `git clean`, so without it only a policy that removes nothing works.
+    async fn clean_preview(&self, _work_dir: &str, policy: &CleanPolicy) -> Result<(Vec<String>, Vec<String>), GitError> {
+        match policy.mode {
+            CleanMode::None => Ok((Vec::new(), Vec::new())),
+            _ => Err(GitError::Unsupported { operation: "clean policies".to_string() }),
+        }
+    }
+}
+
+/// Same shape as git's `iso-strict` dates, so both backends list tags alike.
+fn rfc3339(time: gix::date::Time) -> Option<String> {
+    use chrono::TimeZone;
+    let offset = chrono::FixedOffset::east_opt(time.offset)?;
+    offset.timestamp_opt(time.seconds, 0).single().map(|date| date.to_rfc3339())
+}
+
+/// gix errors carry git's own wording often enough for the stderr classifier to apply.
+fn gix_error(error: impl std::fmt::Display) -> GitError {
+    GitError::from_failure(None, error.to_string())
+}
+
+async fn run_blocking<T, F>(operation: F) -> Result<T, GitError>
+where
+    F: FnOnce() -> Result<T, GitError> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(operation)
+        .await
+        .map_err(|e| GitError::SpawnFailed { message: e.to_string() })?
+}
+
+fn interrupt_on_cancel(mut cancel_rx: watch::Receiver<bool>, interrupt: Arc<AtomicBool>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        if cancel_rx.wait_for(|cancelled| *cancelled).await.is_ok() {
+            interrupt.store(true, Ordering::Relaxed);
+        }
+    })
+}
+
+/// gix starts ssh itself and ignores `GIT_SSH_COMMAND`, so a per-repository key cannot be honoured.
+fn reject_ssh_key(options: &CloneOptions) -> Result<(), GitError> {
+    match options.ssh_key_path.as_deref().filter(|p| !p.is_empty()) {
+        Some(_) => Err(GitError::Unsupported { operation: "per-repository SSH keys".to_string() }),
+        None => Ok(()),
+    }
+}
+
+fn vault_credential(remote_url: &str) -> Option<HostCredential> {
+    credentials::lookup(&credentials::host_of(remote_url)?)
+}
+
+/// Credentials callback handing gix the token saved in the vault, in place of the
+/// git credential helpers it would consult otherwise.
+fn answer_from_vault(credential: HostCredential) -> impl FnMut(gix::credentials::helper::Action) -> gix::credentials::protocol::Result {
+    move |action| match action {
+        gix::credentials::helper::Action::Get(context) => Ok(Some(gix::credentials::protocol::Outcome {
+            identity: gix::sec::identity::Account {
+                username: credential.username.clone(),
+                password: credential.token.clone(),
+                oauth_refresh_token: None,
+            },
+            next: context.into(),
+        })),
+        // Nothing to approve or reject: the vault only changes from settings.
+        _ => Ok(None),
+    }
+}
+
+fn fetch_origin(work_dir: &str, tags: Tags, depth: Option<u32>) -> Result<(), GitError> {
+    let repo = gix::open(work_dir).map_err(gix_error)?;
+    let remote = repo.find_remote("origin").map_err(gix_error)?.with_fetch_tags(tags);
+    let credential = remote
+        .url(Direction::Fetch)
+        .and_then(|url| vault_credential(&url.to_bstring().to_string()));
+
+    let mut connection = remote.connect(Direction::Fetch).map_err(gix_error)?;
+    if let Some(credential) = credential {
+        connection = connection.with_credentials(answer_from_vault(credential));
+    }
+    let mut prepare = connection
+        .prepare_fetch(Discard, Default::default())
+        .map_err(gix_error)?;
+    if let Some(depth) = depth.and_then(NonZeroU32::new) {
+        prepare = prepare.with_shallow(Shallow::DepthAtRemote(depth));
+    }
+
+    prepare.receive(Discard, &AtomicBool::new(false)).map_err(gix_error)?;
+    Ok(())
+}
+
+/// Commits reachable from `tip` but not from any of `hidden`.
+fn count_commits(repo: &gix::Repository, tip: gix::ObjectId, hidden: impl IntoIterator<Item = gix::ObjectId>) -> Result<u32, GitError> {
+    let mut count = 0;
+    for info in repo.rev_walk([tip]).with_hidden(hidden).all().map_err(gix_error)? {
+        info.map_err(gix_error)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Whether a tracked file whose type and mode still match its index `entry` has
+/// other content. Trusts unchanged stat data unless the index was written too soon
+/// after the file to tell.
+fn content_changed(
+    pipeline: &mut gix::filter::Pipeline<'_>,
+    index: &gix::index::State,
+    workdir: &Path,
+    entry: &gix::index::Entry,
+    path: &BStr,
+    metadata: &gix::index::fs::Metadata,
+    options: gix::index::entry::stat::Options,
+) -> Result<bool, GitError> {
+    let stat = Stat::from_fs(metadata).map_err(gix_error)?;
+    if entry.stat.matches(&stat, options) && !entry.stat.is_racy(index.timestamp(), options) {
+        return Ok(false);
+    }
+
+    let relative = gix::path::from_bstr(path);
+    let file = workdir.join(&relative);
+    let mut data = Vec::new();
+    if metadata.is_symlink() {
+        let target = std::fs::read_link(&file).map_err(gix_error)?;
+        data.extend_from_slice(&gix::path::into_bstr(target));
+    } else {
+        let source = std::fs::File::open(&file).map_err(gix_error)?;
+        match pipeline.convert_to_git(source, &relative, index).map_err(gix_error)? {
+            ToGitOutcome::Unchanged(mut read) => {
+                read.read_to_end(&mut data).map_err(gix_error)?;
+            }
+            ToGitOutcome::Process(mut read) => {
+                read.read_to_end(&mut data).map_err(gix_error)?;
+            }
+            ToGitOutcome::Buffer(buffer) => data.extend_from_slice(buffer),
+        }
+    }
+    let id = gix::objs::compute_hash(entry.id.kind(), gix::objs::Kind::Blob, &data).map_err(gix_error)?;
+    Ok(id != entry.id)
+}
+
+/// The index paths and every directory that holds one of them.
+struct Tracked<'a> {
+    files: &'a HashSet<&'a BStr>,
+    dirs: &'a HashSet<&'a BStr>,
+}
+
+/// Collects the untracked, not ignored paths below `dir` (relative with `/`, empty
+/// for the root) into `found`. A directory without tracked files is listed once as
+/// `dir/`, as `git status` does. With `first_only`, stops at the first path found.
+fn untracked_paths(
+    workdir: &Path,
+    dir: &str,
+    tracked: &Tracked<'_>,
+    excludes: &mut gix::AttributeStack<'_>,
+    first_only: bool,
+    found: &mut Vec<String>,
+) -> Result<(), GitError> {
+    for entry in std::fs::read_dir(workdir.join(dir)).map_err(gix_error)? {
+        let entry = entry.map_err(gix_error)?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name == ".git" {
+            continue;
+        }
+        let path = if dir.is_empty() { name } else { format!("{}/{}", dir, name) };
+        if tracked.files.contains(BStr::new(&path)) {
+            continue;
+        }
+        let is_dir = entry.file_type().map_err(gix_error)?.is_dir();
+        if excludes.at_path(&path, is_dir.then_some(Mode::DIR)).map_err(gix_error)?.is_excluded() {
+            continue;
+        }
+
+        if !is_dir {
+            found.push(path);
+        } else if tracked.dirs.contains(BStr::new(&path)) {
+            untracked_paths(workdir, &path, tracked, excludes, first_only, found)?;
+        } else {
+            let mut inside = Vec::new();
+            untracked_paths(workdir, &path, tracked, excludes, true, &mut inside)?;
+            if !inside.is_empty() {
+                found.push(format!("{}/", path));
+            }
+        }
+        if first_only && !found.is_empty() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Makes the working tree and index match `commit_id`, overwriting local changes
+/// and deleting tracked files the commit does not have. HEAD is left to the caller.
+fn checkout_commit(repo: &gix::Repository, commit_id: gix::ObjectId) -> Result<(), GitError> {
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| gix_error("repository has no working tree"))?
+        .to_path_buf();
+    let tree_id = repo
+        .find_commit(commit_id)
+        .map_err(gix_error)?
+        .tree_id()
+        .map_err(gix_error)?
+        .detach();
+    let mut index = repo.index_from_tree(&tree_id).map_err(gix_error)?;
+
+    {
+        let previous = repo.index_or_empty().map_err(gix_error)?;
+        let wanted: HashSet<&BStr> = index.entries().iter().map(|entry| entry.path(&index)).collect();
+        for entry in previous.entries() {
+            let path = entry.path(&previous);
+            if !wanted.contains(path) {
+                std::fs::remove_file(workdir.join(gix::path::from_bstr(path))).ok();
+            }
+        }
+    }
+
+    let mut options = repo
+        .checkout_options(gix::worktree::stack::state::attributes::Source::IdMapping)
+        .map_err(gix_error)?;
+    options.overwrite_existing = true;
+    options.destination_is_initially_empty = false;
+
+    let objects = repo.objects.clone().into_arc().map_err(gix_error)?;
+    gix::worktree::state::checkout(
+        &mut index,
+        &workdir,
+        objects,
+        &Discard,
+        &Discard,
+        &AtomicBool::new(false),
+        options,
+    )
+    .map_err(gix_error)?;
+
+    index.write(Default::default()).map_err(gix_error)?;
+    Ok(())
+}
+
+fn set_head(repo: &gix::Repository, target: Target, message: String) -> Result<(), GitError> {
+    repo.edit_reference(RefEdit {
+        change: Change::Update {
+            log: LogChange {
+                message: message.into(),
+                ..Default::default()
+            },
+            expected: PreviousValue::Any,
+            new: target,
+        },
+        name: "HEAD".try_into().map_err(gix_error)?,
+        deref: false,
+    })
+    .map_err(gix_error)?;
+    Ok(())
+}
+
+/// Samples the gix progress tree until `done` is set and reports it in the same
+/// stages and percentage ranges the CLI parser produces.
+async fn forward_progress(root: Arc<Root>, progress_tx: mpsc::Sender<CloneProgress>, done: Arc<AtomicBool>) {
+    let mut snapshot = Vec::new();
+    let mut last_bytes: Option<(usize, Instant)> = None;
+
+    while !done.load(Ordering::Relaxed) {
+        tokio::time::sleep(PROGRESS_INTERVAL).await;
+        root.sorted_snapshot(&mut snapshot);
+
+        let step = |name: &str| {
+            snapshot
+                .iter()
+                .rev()
+                .find(|(_, task)| task.name == name)
+                .and_then(|(_, task)| task.progress.as_ref())
+                .map(|value| (value.step.load(Ordering::Relaxed), value.done_at))
+        };
+        let percent = |(current, total): (usize, Option<usize>)| {
+            total.filter(|t| *t > 0).map(|t| (current * 100 / t).min(100) as u32).unwrap_or(0)
+        };
+
+        let progress = if let Some(files) = step("checkout") {
+            CloneProgress {
+                stage: "Checkout".to_string(),
+                progress: 90 + percent(files) * 10 / 100,
+                received_objects: None,
+                total_objects: None,
+                received_bytes: None,
+                speed: None,
+                submodule: None,
+            }
+        } else if let Some(resolved) = step("Resolving") {
+            CloneProgress {
+                stage: "Resolving".to_string(),
+                progress: 60 + percent(resolved) * 30 / 100,
+                received_objects: None,
+                total_objects: None,
+                received_bytes: None,
+                speed: None,
+                submodule: None,
+            }
+        } else if let Some(objects) = step("indexing") {
+            let bytes = step("read pack").map(|(bytes, _)| bytes);
+            let speed = match (bytes, last_bytes) {
+                (Some(bytes), Some((previous, at))) if bytes >= previous => {
+                    let per_second = (bytes - previous) as f64 / at.elapsed().as_secs_f64();
+                    Some(format!("{:.2} MiB/s", per_second / (1024.0 * 1024.0)))
+                }
+                _ => None,
+            };
+            last_bytes = bytes.map(|bytes| (bytes, Instant::now()));
+
+            CloneProgress {
+                stage: "Receiving".to_string(),
+                progress: 10 + percent(objects) * 50 / 100,
+                received_objects: Some(objects.0 as u32),
+                total_objects: objects.1.map(|total| total as u32),
+                received_bytes: bytes.map(|bytes| bytes as f64),
+                speed,
+                submodule: None,
+            }
+        } else {
+            continue;
+        };
+
+        if progress_tx.send(progress).await.is_err() {
+            break;
+        }
+    }
+}
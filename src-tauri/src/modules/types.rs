@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use specta::Type;
 use tauri_specta::Event;
+use crate::modules::error::CommandError;
 
 #[derive(Serialize, Deserialize, Type, Clone)]
 pub struct RepositoryInfo {
@@ -20,12 +21,170 @@ pub struct RepositoryInfo {
     pub has_warning: bool,
     #[serde(rename = "lastSyncTime")]
     pub last_sync_time: Option<String>,
+    #[serde(rename = "cloneOptions", default)]
+    pub clone_options: CloneOptions,
+    /// Replaces the global channel rules for this repository when set.
+    #[serde(rename = "channelRules", default)]
+    pub channel_rules: Option<Vec<ChannelRule>>,
+    #[serde(rename = "tagSort", default)]
+    pub tag_sort: TagSortMode,
+    /// Details for each entry of `gameVersions`, `None` for branches.
+    #[serde(rename = "versionDetails", default)]
+    pub version_details: Vec<Option<TagDetails>>,
+    #[serde(rename = "cleanPolicy", default)]
+    pub clean_policy: CleanPolicy,
+    /// What is actually checked out; `None` until the repository has been read.
+    #[serde(default)]
+    pub head: Option<HeadInfo>,
+    /// When the app last cloned or switched this checkout, RFC 3339.
+    #[serde(rename = "switchedAt", default)]
+    pub switched_at: Option<String>,
+    #[serde(default)]
+    pub status: Option<RepositoryStatus>,
+    #[serde(rename = "autoRefresh", default)]
+    pub auto_refresh: AutoRefresh,
+    /// Switches to the newest version of a channel whenever a refresh finds one.
+    #[serde(default)]
+    pub follow: Option<FollowChannel>,
+    /// Automatic switches made or skipped by `follow`, oldest first.
+    #[serde(rename = "followLog", default)]
+    pub follow_log: Vec<FollowRecord>,
+    /// Holds the repository on one version: switches are refused unless overridden.
+    #[serde(default)]
+    pub pin: Option<Pin>,
+}
+
+#[derive(Serialize, Deserialize, Type, Clone, Debug)]
+pub struct Pin {
+    /// The tag or `BRANCH:` entry the repository is held on.
+    pub tag: String,
+    /// The commit `tag` pointed at when pinned.
+    pub commit: String,
+    pub reason: String,
+    /// git's `user.name`, or the OS user when that is not set.
+    #[serde(rename = "pinnedBy")]
+    pub pinned_by: String,
+    /// RFC 3339.
+    #[serde(rename = "pinnedAt")]
+    pub pinned_at: String,
+}
+
+/// How the pinned ref looks on origin, when it no longer matches the pin.
+#[derive(Serialize, Deserialize, Type, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum PinDrift {
+    /// Deleted on origin.
+    Missing,
+    /// Points at another commit on origin.
+    Moved { commit: String },
+}
+
+#[derive(Serialize, Deserialize, Type, Clone, Debug)]
+pub struct FollowChannel {
+    pub channel: String,
+    /// Ignore the channel's branches and follow only its tags.
+    #[serde(rename = "tagsOnly", default)]
+    pub tags_only: bool,
+}
+
+/// One successful clone or version switch of a repository.
+#[derive(Serialize, Deserialize, Type, Clone, Debug)]
+pub struct HistoryEntry {
+    /// RFC 3339.
+    pub at: String,
+    pub source: SwitchSource,
+    /// What was checked out before; `None` for a clone.
+    pub from: Option<HistoryPoint>,
+    pub to: HistoryPoint,
+}
+
+#[derive(Serialize, Deserialize, Type, Clone, Debug)]
+pub struct HistoryPoint {
+    #[serde(rename = "ref")]
+    pub reference: HeadRef,
+    pub commit: String,
+    /// How the version list showed it, e.g. `qa-1.2.0`.
+    #[serde(rename = "displayName")]
+    pub display_name: String,
+}
+
+#[derive(Serialize, Deserialize, Type, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SwitchSource {
+    Clone,
+    /// `change_version`, picked by the user.
+    Manual,
+    /// Follow mode.
+    Automatic,
+    /// `rollback_version`.
+    Rollback,
+}
+
+/// One switch follow mode made, or had to skip.
+#[derive(Serialize, Deserialize, Type, Clone, Debug)]
+pub struct FollowRecord {
+    /// RFC 3339.
+    pub at: String,
+    /// Display name of the version switched away from.
+    pub from: String,
+    /// Display name of the version switched to.
+    pub to: String,
+    /// The tag or `BRANCH:` entry of `to`.
+    pub tag: String,
+    /// Why the switch was not made; `None` when it was.
+    pub skipped: Option<String>,
+}
+
+/// Background refresh of one repository by the scheduler.
+#[derive(Serialize, Deserialize, Type, Clone, Debug, Default)]
+pub struct AutoRefresh {
+    /// Minutes between fetches; `None` leaves the repository to manual refreshes.
+    #[serde(rename = "intervalMinutes")]
+    pub interval_minutes: Option<u32>,
+    /// Channels whose new tags and branches raise `NewVersionAvailableEvent`.
+    /// Empty means the channel of the checked-out version.
+    #[serde(default)]
+    pub channels: Vec<String>,
+}
+
+/// How `refresh_all` fetches every repository, chosen in settings.
+#[derive(Serialize, Deserialize, Type, Clone, Copy, Debug)]
+pub struct RefreshSettings {
+    /// Most repositories fetched at the same time.
+    pub concurrency: u32,
+    /// Refresh everything once when the app starts.
+    #[serde(rename = "onLaunch")]
+    pub on_launch: bool,
+}
+
+impl Default for RefreshSettings {
+    fn default() -> Self {
+        RefreshSettings { concurrency: 4, on_launch: false }
+    }
+}
+
+/// Options for `git clone`, kept on the repository so resumes, tag fetches
+/// and deepening reuse the same settings.
+#[derive(Serialize, Deserialize, Type, Clone, Debug, Default)]
+pub struct CloneOptions {
+    pub depth: Option<u32>,
+    #[serde(rename = "filterBlobs", default)]
+    pub filter_blobs: bool,
+    #[serde(rename = "singleBranch", default)]
+    pub single_branch: bool,
+    pub branch: Option<String>,
+    pub jobs: Option<u32>,
+    /// Cone-mode sparse-checkout directories; empty means a full checkout.
+    #[serde(rename = "sparsePaths", default)]
+    pub sparse_paths: Vec<String>,
+    /// Private key for SSH remotes, passed to git through `GIT_SSH_COMMAND`.
+    #[serde(rename = "sshKeyPath", default)]
+    pub ssh_key_path: Option<String>,
 }
 
 #[derive(Clone, Type, Event)]
 pub struct AppInitializeEvent {
     pub repository_datas: Vec<RepositoryInfo>,
-    pub auth_token: String,
     pub root_path: String,
     pub app_version: String,
 }
@@ -42,19 +201,343 @@ pub struct CloneProgressEvent {
     #[serde(rename = "receivedObjects")]
     pub received_objects: Option<u32>,
     pub speed: Option<String>,
+    pub submodule: Option<String>,
 }
 
 #[derive(Clone, Serialize, Deserialize, Type, Event)]
 pub struct CloneCompleteEvent {
     pub repo_name: String,
     pub success: bool,
+    pub cancelled: bool,
     pub error_message: Option<String>,
 }
 
+/// Emitted when the remote turned git away for lack of credentials, so the frontend
+/// can ask for a token (HTTPS) or an SSH key and retry.
+#[derive(Clone, Serialize, Deserialize, Type, Event)]
+pub struct CredentialRequestEvent {
+    pub repo_name: String,
+    pub remote_url: String,
+    pub host: String,
+    pub kind: CredentialKind,
+}
+
+/// Emitted when a refresh lists a tag or branch that was not there before, on a
+/// channel the repository watches (see `AutoRefresh::channels`).
+#[derive(Clone, Serialize, Deserialize, Type, Event)]
+pub struct NewVersionAvailableEvent {
+    pub repo_id: u32,
+    pub repo_name: String,
+    pub channel: String,
+    pub tag: String,
+    #[serde(rename = "displayName")]
+    pub display_name: String,
+}
+
+/// Emitted when follow mode switches a repository, or skips a switch.
+#[derive(Clone, Serialize, Deserialize, Type, Event)]
+pub struct AutoSwitchEvent {
+    pub repo_id: u32,
+    pub repo_name: String,
+    pub record: FollowRecord,
+    /// The repository after the switch.
+    pub repository: RepositoryInfo,
+}
+
+/// Emitted by `refresh_all` and scheduled refreshes as each repository finishes,
+/// so the list can update before the slowest remote answers.
+#[derive(Clone, Serialize, Type, Event)]
+pub struct RepositoryRefreshedEvent {
+    pub repo_id: u32,
+    pub repo_name: String,
+    /// The refreshed repository; `None` when the refresh failed.
+    pub repository: Option<RepositoryInfo>,
+    pub error: Option<CommandError>,
+}
+
+#[derive(Serialize, Deserialize, Type, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum CredentialKind {
+    Https,
+    Ssh,
+}
+
+/// A saved HTTPS credential as shown in settings; the token itself never leaves the backend.
+#[derive(Serialize, Deserialize, Type, Clone)]
+pub struct CredentialSummary {
+    pub host: String,
+    pub username: String,
+}
+
+/// A clone that has been started, persisted so it can be resumed after an app restart.
+#[derive(Serialize, Deserialize, Type, Clone)]
+pub struct PendingClone {
+    pub name: String,
+    pub remote_url: String,
+    #[serde(rename = "tempPath")]
+    pub temp_path: String,
+    #[serde(rename = "startedAt")]
+    pub started_at: String,
+    #[serde(default)]
+    pub options: CloneOptions,
+}
+
+#[derive(Serialize, Deserialize, Type, Clone)]
+pub struct InterruptedClone {
+    pub name: String,
+    pub remote_url: Option<String>,
+    #[serde(rename = "tempPath")]
+    pub temp_path: String,
+    #[serde(rename = "startedAt")]
+    pub started_at: Option<String>,
+    #[serde(rename = "hasPartialData")]
+    pub has_partial_data: bool,
+}
+
+/// Which implementation runs git operations, chosen in settings.
+#[derive(Serialize, Deserialize, Type, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum GitBackendKind {
+    /// The git executable in PATH.
+    #[default]
+    Cli,
+    /// The built-in gix implementation; works without git installed.
+    Gix,
+}
+
+/// How the tags in a repository's version list are ordered, newest first.
+#[derive(Serialize, Deserialize, Type, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum TagSortMode {
+    /// By the version in the tag name, so re-tagging an old build does not move it up.
+    Version,
+    /// By when the tag was created, or for lightweight tags when their commit was
+    /// (`git tag --sort=-creatordate`).
+    #[default]
+    CreatorDate,
+    /// By when the tag was created; lightweight tags have no tagger date and come last.
+    TaggerDate,
+}
+
+/// Maps tags or branches to a release channel and the name its versions are shown with.
+#[derive(Serialize, Deserialize, Type, Clone, Debug)]
+pub struct ChannelRule {
+    pub channel: String,
+    pub matcher: ChannelMatcher,
+    /// `{channel}`, `{name}` (the tag or branch) and `{version}` are filled in,
+    /// e.g. `{channel}-{version}` or `{channel}-latest`.
+    #[serde(rename = "displayTemplate")]
+    pub display_template: String,
+}
+
+#[derive(Serialize, Deserialize, Type, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ChannelMatcher {
+    /// Tags matching this case-insensitive regex. A `version` capture group, if
+    /// present, is used for `{version}`.
+    Tag { regex: String },
+    /// Remote branches matching this name, where `*` matches anything (`release/*`)
+    /// and `?` any one character.
+    Branch { pattern: String },
+}
+
 #[derive(Serialize, Deserialize, Type, Clone)]
 pub struct TagInfo {
     #[serde(rename = "originalTag")]
     pub original_tag: String,
     #[serde(rename = "displayName")]
     pub display_name: String,
+    pub channel: String,
+    /// The version taken from the name, e.g. `2.0.0-rc1`; used for version ordering.
+    pub version: Option<String>,
+    /// Missing for branch entries.
+    pub details: Option<TagDetails>,
+}
+
+/// The commit a tag points at and, for annotated tags, who tagged it and why.
+/// Dates are RFC 3339 with the author's offset.
+#[derive(Serialize, Deserialize, Type, Clone, Debug)]
+pub struct TagDetails {
+    pub commit: String,
+    #[serde(rename = "commitDate")]
+    pub commit_date: Option<String>,
+    #[serde(rename = "tagDate")]
+    pub tag_date: Option<String>,
+    /// `Name <email>`.
+    pub tagger: Option<String>,
+    /// The annotated tag's message, e.g. release notes.
+    pub message: Option<String>,
+}
+
+/// Local state of a checkout, recomputed on refresh and after every switch.
+#[derive(Serialize, Deserialize, Type, Clone, Debug, Default)]
+pub struct RepositoryStatus {
+    /// Tracked files with changes in the working tree.
+    pub modified: u32,
+    /// Files with changes staged in the index.
+    pub staged: u32,
+    pub untracked: u32,
+    /// Commits on the checked-out branch that its upstream lacks; `None` off a tracking branch.
+    pub ahead: Option<u32>,
+    /// Commits on the upstream that the checked-out branch lacks; `None` off a tracking branch.
+    pub behind: Option<u32>,
+    /// Display names of listed tags created after `switchedAt`, in list order.
+    #[serde(rename = "newTags")]
+    pub new_tags: Vec<String>,
+    /// Set by a refresh when the pinned tag or branch changed on origin.
+    #[serde(rename = "pinDrift", default)]
+    pub pin_drift: Option<PinDrift>,
+}
+
+/// The checked-out commit and the ref it was reached through.
+#[derive(Serialize, Deserialize, Type, Clone, Debug)]
+pub struct HeadInfo {
+    #[serde(rename = "ref")]
+    pub reference: HeadRef,
+    pub commit: String,
+    pub subject: String,
+    /// Committer date, RFC 3339.
+    pub date: String,
+}
+
+#[derive(Serialize, Deserialize, Type, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum HeadRef {
+    /// A local branch and the remote branch it tracks, e.g. `origin/main`.
+    Branch { name: String, upstream: Option<String> },
+    /// A detached HEAD with a tag pointing at it.
+    Tag { name: String },
+    /// Any other commit, named by `git describe` (`v1.2.0-3-gabc1234`) or its short hash.
+    Detached { describe: String },
+}
+
+/// What switching from one version to another brings in and takes away.
+#[derive(Serialize, Deserialize, Type, Clone)]
+pub struct Changelog {
+    /// Commits in the target that the current version lacks, newest first.
+    pub added: Vec<ChangelogCommit>,
+    /// Commits in the current version that the target lacks, e.g. when going back.
+    pub removed: Vec<ChangelogCommit>,
+    /// Set when either list was cut short.
+    pub truncated: bool,
+}
+
+#[derive(Serialize, Deserialize, Type, Clone)]
+pub struct ChangelogCommit {
+    pub hash: String,
+    pub author: String,
+    /// RFC 3339 author date.
+    pub date: String,
+    pub subject: String,
+    #[serde(rename = "isMerge")]
+    pub is_merge: bool,
+    /// The merge commit on the main line that brought this commit in, for grouping
+    /// a merged branch under its merge. `None` for main-line commits.
+    #[serde(rename = "mergedBy")]
+    pub merged_by: Option<String>,
+    #[serde(rename = "filesChanged")]
+    pub files_changed: u32,
+    pub insertions: u32,
+    pub deletions: u32,
+}
+
+/// Everything a version switch would throw away, so it can be shown before confirming.
+#[derive(Serialize, Deserialize, Type, Clone)]
+pub struct SwitchPreflight {
+    /// Tracked files changed in the working tree.
+    pub modified: Vec<String>,
+    pub staged: Vec<String>,
+    /// Untracked files the clean policy removes; directories are listed once with a
+    /// trailing `/`. Empty when the policy keeps untracked files.
+    pub untracked: Vec<String>,
+    /// Ignored files the clean policy removes, only with `UntrackedAndIgnored`.
+    /// Backups leave ignored files out, so these cannot be restored.
+    pub ignored: Vec<String>,
+    /// Tracked files marked assume-unchanged or skip-worktree, whose local edits
+    /// git does not report but a reset still overwrites.
+    #[serde(rename = "ignoredTracked")]
+    pub ignored_tracked: Vec<String>,
+    /// Commits in HEAD that no remote branch or tag contains.
+    #[serde(rename = "localCommits")]
+    pub local_commits: Vec<ChangelogCommit>,
+    /// Set when a list was cut short.
+    pub truncated: bool,
+}
+
+/// How `change_version` treats local changes before resetting the checkout.
+#[derive(Serialize, Deserialize, Type, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SwitchMode {
+    /// Throw local changes away.
+    #[default]
+    Discard,
+    /// Keep local changes and commits in a backup ref first, see `list_backups`.
+    Backup,
+}
+
+/// What `change_version` removes from the working tree after checking out.
+#[derive(Serialize, Deserialize, Type, Clone, Debug, Default)]
+pub struct CleanPolicy {
+    pub mode: CleanMode,
+    /// gitignore-style patterns that are never cleaned, e.g. `config/local.json` or `.vscode/`.
+    #[serde(default)]
+    pub preserve: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Type, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum CleanMode {
+    /// Leave untracked files alone.
+    #[default]
+    None,
+    /// Remove untracked files and directories, keeping ignored ones such as build caches.
+    Untracked,
+    /// Also remove ignored files, for a checkout identical to the version.
+    UntrackedAndIgnored,
+}
+
+/// A snapshot of local changes taken before a switch, kept under `refs/repohub/backups/`.
+#[derive(Serialize, Deserialize, Type, Clone)]
+pub struct BackupInfo {
+    /// Full ref name, e.g. `refs/repohub/backups/20261018-141502`.
+    pub name: String,
+    pub commit: String,
+    /// The commit that was checked out when the backup was taken.
+    pub base: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+    pub message: String,
+}
+
+/// A tag as listed by a backend, before channel rules are applied.
+#[derive(Clone, Debug)]
+pub struct TagRef {
+    pub name: String,
+    pub details: TagDetails,
+}
+
+/// A search over every tag of a repository, beyond the short list kept on it.
+#[derive(Serialize, Deserialize, Type, Clone, Debug, Default)]
+pub struct TagQuery {
+    /// Case-insensitive text matched against tag and display names. A version like
+    /// `1.4.7` also lists the tags with exactly that version first.
+    #[serde(default)]
+    pub search: Option<String>,
+    #[serde(default)]
+    pub channel: Option<String>,
+    /// Also list tags no channel rule matches, under an empty channel.
+    #[serde(rename = "includeAll", default)]
+    pub include_all: bool,
+    #[serde(default)]
+    pub offset: u32,
+    pub limit: u32,
+}
+
+#[derive(Serialize, Deserialize, Type, Clone)]
+pub struct TagPage {
+    pub tags: Vec<TagInfo>,
+    /// Number of entries matching the query, across all pages.
+    pub total: u32,
+    #[serde(rename = "hasMore")]
+    pub has_more: bool,
 }
\ No newline at end of file
@@ -0,0 +1,260 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+const PRE_RELEASE_WORDS: &[&str] = &["alpha", "a", "beta", "b", "rc", "pre", "preview", "snapshot", "nightly", "canary"];
+
+/// A version found inside a tag or branch name: any number of dot-separated
+/// numbers (`1.4.19.2`), a pre-release (`2.0.0-rc1`), build metadata (`1.2.0+512`),
+/// or a date (`20261001`, `2026-10-01`) with an optional build counter after it.
+#[derive(Debug, Clone)]
+pub struct Version {
+    raw: String,
+    numbers: Vec<u64>,
+    pre_release: Vec<String>,
+    build: Option<String>,
+}
+
+impl Version {
+    /// The first version in `name`, e.g. `2.0.0-rc1` in `qa-2.0.0-rc1`.
+    pub fn find(name: &str) -> Option<Version> {
+        let start = name.find(|c: char| c.is_ascii_digit())?;
+        let text = &name[start..];
+        Self::parse_date(text).or_else(|| Self::parse_numeric(text))
+    }
+
+    /// `YYYYMMDD` or `YYYY-MM-DD` (also with `.` or `_`), then optionally
+    /// `.N`, `-N` or `_N` as a build counter for that day.
+    fn parse_date(text: &str) -> Option<Version> {
+        let bytes = text.as_bytes();
+        let digits = |range: std::ops::Range<usize>| -> Option<u64> {
+            let part = text.get(range)?;
+            if part.bytes().all(|b| b.is_ascii_digit()) { part.parse().ok() } else { None }
+        };
+
+        let year = digits(0..4)?;
+        let (month, day, mut end) = match bytes.get(4) {
+            Some(b'-' | b'.' | b'_') if bytes.get(7) == Some(&bytes[4]) => (digits(5..7)?, digits(8..10)?, 10),
+            _ => (digits(4..6)?, digits(6..8)?, 8),
+        };
+        // A longer digit run is a plain number, not a date.
+        if bytes.get(end).is_some_and(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        if !(1900..=2999).contains(&year) || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return None;
+        }
+
+        let mut numbers = vec![year, month, day];
+        while let Some(b'.' | b'-' | b'_') = bytes.get(end) {
+            let run = bytes[end + 1..].iter().take_while(|b| b.is_ascii_digit()).count();
+            if run == 0 {
+                break;
+            }
+            numbers.push(text[end + 1..end + 1 + run].parse().ok()?);
+            end += 1 + run;
+        }
+
+        Some(Version { raw: text[..end].to_string(), numbers, pre_release: Vec::new(), build: None })
+    }
+
+    /// `1.4.19.2`, `2.0.0-rc.1`, `2.0.0rc1` or `1.2.0+512`.
+    fn parse_numeric(text: &str) -> Option<Version> {
+        let bytes = text.as_bytes();
+        let mut end = 0;
+        let mut numbers = Vec::new();
+        loop {
+            let run = bytes[end..].iter().take_while(|b| b.is_ascii_digit()).count();
+            numbers.push(text[end..end + run].parse().unwrap_or(u64::MAX));
+            end += run;
+            if bytes.get(end) == Some(&b'.') && bytes.get(end + 1).is_some_and(|b| b.is_ascii_digit()) {
+                end += 1;
+            } else {
+                break;
+            }
+        }
+
+        let mut pre_release = Vec::new();
+        let pre_start = match bytes.get(end) {
+            Some(b'-') if bytes.get(end + 1).is_some_and(|b| b.is_ascii_alphanumeric()) => Some(end + 1),
+            Some(b) if b.is_ascii_alphabetic() => Some(end),
+            _ => None,
+        };
+        if let Some(pre_start) = pre_start {
+            let len = text[pre_start..]
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '.' || c == '-'))
+                .unwrap_or(text.len() - pre_start);
+            let pre = text[pre_start..pre_start + len].trim_end_matches(['.', '-']);
+            if is_pre_release(pre) {
+                pre_release = pre.split(['.', '-']).map(str::to_string).collect();
+                end = pre_start + pre.len();
+            }
+        }
+
+        let mut build = None;
+        if bytes.get(end) == Some(&b'+') {
+            let len = text[end + 1..]
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '.' || c == '-'))
+                .unwrap_or(text.len() - end - 1);
+            if len > 0 {
+                build = Some(text[end + 1..end + 1 + len].to_string());
+                end += 1 + len;
+            }
+        }
+
+        Some(Version { raw: text[..end].to_string(), numbers, pre_release, build })
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+/// Numbers compare as if padded with zeros (`1.4` == `1.4.0`), a release sorts
+/// above its pre-releases, and pre-release parts compare like `rc2` < `rc10`.
+/// A higher build number wins between otherwise equal versions.
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let len = self.numbers.len().max(other.numbers.len());
+        let number = |v: &Version, i: usize| v.numbers.get(i).copied().unwrap_or(0);
+        (0..len)
+            .map(|i| number(self, i).cmp(&number(other, i)))
+            .find(|o| o.is_ne())
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| match (self.pre_release.is_empty(), other.pre_release.is_empty()) {
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => compare_parts(&self.pre_release, &other.pre_release),
+            })
+            .then_with(|| match (&self.build, &other.build) {
+                (Some(a), Some(b)) => natural_cmp(a, b),
+                (a, b) => a.is_some().cmp(&b.is_some()),
+            })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Version {}
+
+/// Only well-known pre-release words count, so a channel suffix like the `qa`
+/// in `1.2.0-qa` stays out of the version.
+fn is_pre_release(pre: &str) -> bool {
+    let word: String = pre
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    PRE_RELEASE_WORDS.contains(&word.as_str())
+}
+
+/// Semver precedence: part by part, numbers below words, and more parts win a tie.
+fn compare_parts(a: &[String], b: &[String]) -> Ordering {
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| match (x.parse::<u64>(), y.parse::<u64>()) {
+            (Ok(x), Ok(y)) => x.cmp(&y),
+            (Ok(_), Err(_)) => Ordering::Less,
+            (Err(_), Ok(_)) => Ordering::Greater,
+            (Err(_), Err(_)) => natural_cmp(x, y),
+        })
+        .find(|o| o.is_ne())
+        .unwrap_or_else(|| a.len().cmp(&b.len()))
+}
+
+/// Case-insensitive comparison where digit runs compare by value, so `rc2` < `rc10`.
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a, b);
+    loop {
+        let (Some(x), Some(y)) = (a.chars().next(), b.chars().next()) else {
+            return a.len().cmp(&b.len());
+        };
+        let ordering = if x.is_ascii_digit() && y.is_ascii_digit() {
+            let (x_len, y_len) = (digit_run(a), digit_run(b));
+            let (x_num, y_num) = (a[..x_len].trim_start_matches('0'), b[..y_len].trim_start_matches('0'));
+            let ordering = x_num.len().cmp(&y_num.len()).then_with(|| x_num.cmp(y_num));
+            a = &a[x_len..];
+            b = &b[y_len..];
+            ordering
+        } else {
+            a = &a[x.len_utf8()..];
+            b = &b[y.len_utf8()..];
+            x.to_ascii_lowercase().cmp(&y.to_ascii_lowercase())
+        };
+        if ordering.is_ne() {
+            return ordering;
+        }
+    }
+}
+
+fn digit_run(s: &str) -> usize {
+    s.bytes().take_while(|b| b.is_ascii_digit()).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_version_in_name() {
+        let cases: &[(&str, Option<&str>)] = &[
+            ("dev-1.4.19.2", Some("1.4.19.2")),
+            ("qa-2.0.0-rc1", Some("2.0.0-rc1")),
+            ("v1.2.0+512", Some("1.2.0+512")),
+            ("release-1.2.0-qa", Some("1.2.0")),
+            ("dev_20261001", Some("20261001")),
+            ("build-2026-10-01_3", Some("2026-10-01_3")),
+            ("main", None),
+            ("feature/login", None),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(Version::find(name).map(|v| v.to_string()).as_deref(), *expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn splits_numbers_and_dates() {
+        assert_eq!(Version::find("dev-1.4.19.2").unwrap().numbers, [1, 4, 19, 2]);
+        assert_eq!(Version::find("dev_20261001").unwrap().numbers, [2026, 10, 1]);
+        assert_eq!(Version::find("qa-2.0.0-rc1").unwrap().pre_release, ["rc1"]);
+    }
+
+    #[test]
+    fn orders_versions() {
+        let ascending = [
+            ("qa-2.0.0-rc1", "qa-2.0.0-rc2"),
+            ("qa-2.0.0-rc2", "qa-2.0.0-rc10"),
+            ("2.0.0-beta", "2.0.0-rc1"),
+            ("2.0.0-rc10", "2.0.0"),
+            ("1.4.19", "1.4.19.2"),
+            ("1.9", "1.10"),
+            ("1.2.0", "1.2.0+1"),
+            ("1.2.0+9", "1.2.0+10"),
+            ("dev_20260930", "dev_20261001"),
+            ("2026-10-01", "2026-10-01.2"),
+        ];
+        for (lower, higher) in ascending {
+            let (a, b) = (Version::find(lower).unwrap(), Version::find(higher).unwrap());
+            assert!(a < b, "{} < {}", lower, higher);
+            assert!(b > a, "{} > {}", higher, lower);
+        }
+    }
+
+    #[test]
+    fn pads_numbers_with_zeros() {
+        assert_eq!(Version::find("1.4").unwrap(), Version::find("1.4.0").unwrap());
+        assert_eq!(Version::find("v1.2.0").unwrap(), Version::find("release-1.2.0").unwrap());
+    }
+}
@@ -0,0 +1,48 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use crate::AppState;
+use crate::command::{refresh_permits, refresh_repositories};
+
+/// How often the scheduler looks for repositories that are due.
+const TICK: Duration = Duration::from_secs(30);
+
+/// Runs for the lifetime of the app. A repository is first refreshed one interval
+/// after the scheduler sees it, so launching the app does not fetch everything at once.
+/// Due repositories are refreshed in the background with the permits `refresh_all`
+/// uses, so a slow remote does not hold up the next tick.
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut last_run: HashMap<u32, Instant> = HashMap::new();
+        let mut ticker = tokio::time::interval(TICK);
+        loop {
+            ticker.tick().await;
+            let (due, permits) = {
+                let state = app.state::<Mutex<AppState>>();
+                let mut state = state.lock().unwrap();
+                let now = Instant::now();
+                last_run.retain(|id, _| state.local_repositories.iter().any(|r| r.id == *id));
+                let due: Vec<u32> = state.local_repositories
+                    .iter()
+                    .filter(|repo| !repo.path.is_empty())
+                    .filter_map(|repo| {
+                        let interval = Duration::from_secs(u64::from(repo.auto_refresh.interval_minutes?) * 60);
+                        let last = *last_run.entry(repo.id).or_insert(now);
+                        (now.duration_since(last) >= interval).then_some(repo.id)
+                    })
+                    .collect();
+                (due, refresh_permits(&mut state))
+            };
+
+            if due.is_empty() {
+                continue;
+            }
+            for repo_id in &due {
+                last_run.insert(*repo_id, Instant::now());
+            }
+            // Repositories still busy from an earlier tick are skipped by their guard.
+            tauri::async_runtime::spawn(refresh_repositories(app.clone(), due, permits));
+        }
+    });
+}
@@ -14,12 +14,157 @@ async getRootPath() : Promise<string> {
 async setRootPath(path: string) : Promise<string> {
     return await TAURI_INVOKE("set_root_path", { path });
 },
+async getGitBackend() : Promise<GitBackendKind> {
+    return await TAURI_INVOKE("get_git_backend");
+},
+async setGitBackend(kind: GitBackendKind) : Promise<Result<GitBackendKind, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_git_backend", { kind }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Backends usable on this machine, so settings can warn when git is missing from PATH.
+ */
+async getAvailableGitBackends() : Promise<GitBackendKind[]> {
+    return await TAURI_INVOKE("get_available_git_backends");
+},
+async getRefreshSettings() : Promise<RefreshSettings> {
+    return await TAURI_INVOKE("get_refresh_settings");
+},
+async setRefreshSettings(settings: RefreshSettings) : Promise<Result<RefreshSettings, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_refresh_settings", { settings }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getCredentials() : Promise<Result<CredentialSummary[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_credentials") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Saves an HTTPS username and token for `host` in the encrypted vault, replacing any previous one.
+ */
+async saveCredential(host: string, username: string, token: string) : Promise<Result<CredentialSummary[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("save_credential", { host, username, token }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async deleteCredential(host: string) : Promise<Result<CredentialSummary[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_credential", { host }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getChannelRules() : Promise<ChannelRule[]> {
+    return await TAURI_INVOKE("get_channel_rules");
+},
+/**
+ * Replaces the global channel rules. Repositories pick them up on their next refresh.
+ */
+async setChannelRules(rules: ChannelRule[]) : Promise<Result<ChannelRule[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_channel_rules", { rules }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Gives a repository its own channel rules, or makes it follow the global ones again with `None`.
+ */
+async setRepositoryChannelRules(repoId: number, rules: ChannelRule[] | null) : Promise<Result<RepositoryInfo, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_repository_channel_rules", { repoId, rules }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Changes how a repository's tags are ordered. Takes effect on its next refresh.
+ */
+async setTagSort(repoId: number, sort: TagSortMode) : Promise<Result<RepositoryInfo, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_tag_sort", { repoId, sort }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Sets what `change_version` cleans from the working tree after a switch.
+ */
+async setCleanPolicy(repoId: number, policy: CleanPolicy) : Promise<Result<RepositoryInfo, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_clean_policy", { repoId, policy }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Sets how often the background scheduler refreshes a repository and which
+ * channels it reports new versions for.
+ */
+async setAutoRefresh(repoId: number, settings: AutoRefresh) : Promise<Result<RepositoryInfo, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_auto_refresh", { repoId, settings }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Puts a repository in follow mode for `follow.channel`, or takes it out with `None`.
+ * The switch itself happens on the next refresh.
+ */
+async setFollowChannel(repoId: number, follow: FollowChannel | null) : Promise<Result<RepositoryInfo, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_follow_channel", { repoId, follow }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Pins a repository to the tag or branch it has checked out, recording why and by whom.
+ */
+async pinRepository(repoId: number, reason: string) : Promise<Result<RepositoryInfo, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("pin_repository", { repoId, reason }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async unpinRepository(repoId: number) : Promise<Result<RepositoryInfo, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("unpin_repository", { repoId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
 async addProject(remoteUrl: string, projectName: string) : Promise<RepositoryInfo[]> {
     return await TAURI_INVOKE("add_project", { remoteUrl, projectName });
 },
-async cloneRepository(remoteUrl: string, projectName: string) : Promise<Result<RepositoryInfo, string>> {
+async cloneRepository(remoteUrl: string, projectName: string, options: CloneOptions | null) : Promise<Result<RepositoryInfo, CommandError>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("clone_repository", { remoteUrl, projectName }) };
+    return { status: "ok", data: await TAURI_INVOKE("clone_repository", { remoteUrl, projectName, options }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
@@ -33,10 +178,50 @@ async validateRepoName(name: string) : Promise<Result<boolean, string>> {
     else return { status: "error", error: e  as any };
 }
 },
+async cancelClone(repoName: string) : Promise<Result<boolean, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("cancel_clone", { repoName }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Lists clones that were interrupted by an app exit: pending records that are no longer
+ * running, plus any `.tmp_<name>` directory left under the root path without a record.
+ */
+async getInterruptedClones() : Promise<Result<InterruptedClone[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_interrupted_clones") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Continues an interrupted clone by fetching into the partial repository left in
+ * `.tmp_<name>`, then finishes it exactly like a fresh clone.
+ */
+async resumeClone(repoName: string) : Promise<Result<RepositoryInfo, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("resume_clone", { repoName }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async discardInterruptedClone(repoName: string) : Promise<Result<boolean, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("discard_interrupted_clone", { repoName }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
 async getRepositories() : Promise<RepositoryInfo[]> {
     return await TAURI_INVOKE("get_repositories");
 },
-async getFilteredTags(repoPath: string) : Promise<Result<TagInfo[], string>> {
+async getFilteredTags(repoPath: string) : Promise<Result<TagInfo[], CommandError>> {
     try {
     return { status: "ok", data: await TAURI_INVOKE("get_filtered_tags", { repoPath }) };
 } catch (e) {
@@ -44,7 +229,41 @@ async getFilteredTags(repoPath: string) : Promise<Result<TagInfo[], string>> {
     else return { status: "error", error: e  as any };
 }
 },
-async refreshRepository(repoId: number) : Promise<Result<RepositoryInfo, string>> {
+/**
+ * Searches every tag of a repository, a page at a time.
+ */
+async queryTags(repoId: number, query: TagQuery) : Promise<Result<TagPage, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("query_tags", { repoId, query }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Finds the tag for a typed version like `1.4.7`, so it can be switched to directly.
+ */
+async resolveVersion(repoId: number, version: string) : Promise<Result<TagInfo, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("resolve_version", { repoId, version }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Commits between two version list entries (tags, `BRANCH:` entries or commit
+ * hashes), for showing what a switch would change.
+ */
+async getChangelog(repoId: number, from: string, to: string) : Promise<Result<Changelog, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_changelog", { repoId, from, to }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async refreshRepository(repoId: number) : Promise<Result<RepositoryInfo, CommandError>> {
     try {
     return { status: "ok", data: await TAURI_INVOKE("refresh_repository", { repoId }) };
 } catch (e) {
@@ -52,9 +271,140 @@ async refreshRepository(repoId: number) : Promise<Result<RepositoryInfo, string>
     else return { status: "error", error: e  as any };
 }
 },
-async changeVersion(repoId: number, tag: string) : Promise<Result<RepositoryInfo, string>> {
+/**
+ * Refreshes every cloned repository, at most `concurrency` at a time (the saved
+ * `RefreshSettings::concurrency` when `None`), and returns the list afterwards.
+ * Each result is also emitted as a `RepositoryRefreshedEvent` as soon as it is in.
+ */
+async refreshAll(concurrency: number | null) : Promise<Result<RepositoryInfo[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("refresh_all", { concurrency }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * The launch refresh, run once per app start when `RefreshSettings::on_launch` is
+ * set. The page calls it after attaching its event listeners, so no
+ * `RepositoryRefreshedEvent` goes out before anyone listens. Returns the list afterwards.
+ */
+async refreshOnLaunch() : Promise<Result<RepositoryInfo[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("refresh_on_launch") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Extends the history of a shallow clone by `depth` commits, or unshallows it
+ * completely when `depth` is `None`. The stored clone options follow along.
+ */
+async deepenRepository(repoId: number, depth: number | null) : Promise<Result<RepositoryInfo, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("deepen_repository", { repoId, depth }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Replaces the sparse-checkout profile of a repository and applies it right away.
+ * An empty list turns sparse-checkout off and restores the full tree.
+ */
+async setSparsePaths(repoId: number, paths: string[]) : Promise<Result<RepositoryInfo, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_sparse_paths", { repoId, paths }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Re-applies the saved sparse-checkout profile, e.g. after the tree was changed outside the app.
+ */
+async applySparseCheckout(repoId: number) : Promise<Result<RepositoryInfo, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("apply_sparse_checkout", { repoId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Sets or clears the SSH private key used for the repository's remote. It is stored
+ * with the clone options, so fetches, switches and submodule updates all use it.
+ */
+async setSshKey(repoId: number, keyPath: string | null) : Promise<Result<RepositoryInfo, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_ssh_key", { repoId, keyPath }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Lists what `change_version` would discard in a repository.
+ */
+async preflightSwitch(repoId: number) : Promise<Result<SwitchPreflight, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("preflight_switch", { repoId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async changeVersion(repoId: number, tag: string, mode: SwitchMode, overridePin: boolean) : Promise<Result<RepositoryInfo, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("change_version", { repoId, tag, mode, overridePin }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Returns a repository to what it had checked out before its latest clone or switch.
+ */
+async rollbackVersion(repoId: number, mode: SwitchMode, overridePin: boolean) : Promise<Result<RepositoryInfo, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("rollback_version", { repoId, mode, overridePin }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Clones and switches of a repository, newest first.
+ */
+async getVersionHistory(repoId: number) : Promise<HistoryEntry[]> {
+    return await TAURI_INVOKE("get_version_history", { repoId });
+},
+/**
+ * Backups taken by `change_version` in backup mode, newest first.
+ */
+async listBackups(repoId: number) : Promise<Result<BackupInfo[], CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_backups", { repoId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Re-applies a backup's changes on top of the current checkout.
+ */
+async restoreBackup(repoId: number, name: string) : Promise<Result<boolean, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("restore_backup", { repoId, name }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async deleteBackup(repoId: number, name: string) : Promise<Result<BackupInfo[], CommandError>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("change_version", { repoId, tag }) };
+    return { status: "ok", data: await TAURI_INVOKE("delete_backup", { repoId, name }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
@@ -107,12 +457,20 @@ async resetAppData() : Promise<Result<boolean, string>> {
 
 export const events = __makeEvents__<{
 appInitializeEvent: AppInitializeEvent,
+autoSwitchEvent: AutoSwitchEvent,
 cloneCompleteEvent: CloneCompleteEvent,
-cloneProgressEvent: CloneProgressEvent
+cloneProgressEvent: CloneProgressEvent,
+credentialRequestEvent: CredentialRequestEvent,
+newVersionAvailableEvent: NewVersionAvailableEvent,
+repositoryRefreshedEvent: RepositoryRefreshedEvent
 }>({
 appInitializeEvent: "app-initialize-event",
+autoSwitchEvent: "auto-switch-event",
 cloneCompleteEvent: "clone-complete-event",
-cloneProgressEvent: "clone-progress-event"
+cloneProgressEvent: "clone-progress-event",
+credentialRequestEvent: "credential-request-event",
+newVersionAvailableEvent: "new-version-available-event",
+repositoryRefreshedEvent: "repository-refreshed-event"
 })
 
 /** user-defined constants **/
@@ -121,11 +479,457 @@ cloneProgressEvent: "clone-progress-event"
 
 /** user-defined types **/
 
-export type AppInitializeEvent = { repository_datas: RepositoryInfo[]; auth_token: string; root_path: string; app_version: string }
-export type CloneCompleteEvent = { repo_name: string; success: boolean; error_message: string | null }
-export type CloneProgressEvent = { repo_name: string; progress: number; message: string; receivedBytes: number | null; totalObjects: number | null; receivedObjects: number | null; speed: string | null }
-export type RepositoryInfo = { id: number; name: string; remote_url: string; branch: string; path: string; gameVersion: string; gameVersions: string[]; server: string; serverOptions: string[]; hasWarning: boolean; lastSyncTime: string | null }
-export type TagInfo = { originalTag: string; displayName: string }
+export type AppInitializeEvent = { repository_datas: RepositoryInfo[]; root_path: string; app_version: string }
+/**
+ * Background refresh of one repository by the scheduler.
+ */
+export type AutoRefresh = { 
+/**
+ * Minutes between fetches; `None` leaves the repository to manual refreshes.
+ */
+intervalMinutes: number | null; 
+/**
+ * Channels whose new tags and branches raise `NewVersionAvailableEvent`.
+ * Empty means the channel of the checked-out version.
+ */
+channels: string[] }
+/**
+ * Emitted when follow mode switches a repository, or skips a switch.
+ */
+export type AutoSwitchEvent = { repo_id: number; repo_name: string; record: FollowRecord; 
+/**
+ * The repository after the switch.
+ */
+repository: RepositoryInfo }
+/**
+ * A snapshot of local changes taken before a switch, kept under `refs/repohub/backups/`.
+ */
+export type BackupInfo = { 
+/**
+ * Full ref name, e.g. `refs/repohub/backups/20261018-141502`.
+ */
+name: string; commit: string; 
+/**
+ * The commit that was checked out when the backup was taken.
+ */
+base: string; createdAt: string; message: string }
+/**
+ * What switching from one version to another brings in and takes away.
+ */
+export type Changelog = { 
+/**
+ * Commits in the target that the current version lacks, newest first.
+ */
+added: ChangelogCommit[]; 
+/**
+ * Commits in the current version that the target lacks, e.g. when going back.
+ */
+removed: ChangelogCommit[]; 
+/**
+ * Set when either list was cut short.
+ */
+truncated: boolean }
+export type ChangelogCommit = { hash: string; author: string; 
+/**
+ * RFC 3339 author date.
+ */
+date: string; subject: string; isMerge: boolean; 
+/**
+ * The merge commit on the main line that brought this commit in, for grouping
+ * a merged branch under its merge. `None` for main-line commits.
+ */
+mergedBy: string | null; filesChanged: number; insertions: number; deletions: number }
+export type ChannelMatcher = /**
+ * Tags matching this case-insensitive regex. A `version` capture group, if
+ * present, is used for `{version}`.
+ */
+{ kind: "tag"; regex: string } | /**
+ * Remote branches matching this name, where `*` matches anything (`release/*`)
+ * and `?` any one character.
+ */
+{ kind: "branch"; pattern: string }
+/**
+ * Maps tags or branches to a release channel and the name its versions are shown with.
+ */
+export type ChannelRule = { channel: string; matcher: ChannelMatcher; 
+/**
+ * `{channel}`, `{name}` (the tag or branch) and `{version}` are filled in,
+ * e.g. `{channel}-{version}` or `{channel}-latest`.
+ */
+displayTemplate: string }
+export type CleanMode = /**
+ * Leave untracked files alone.
+ */
+"none" | /**
+ * Remove untracked files and directories, keeping ignored ones such as build caches.
+ */
+"untracked" | /**
+ * Also remove ignored files, for a checkout identical to the version.
+ */
+"untrackedAndIgnored"
+/**
+ * What `change_version` removes from the working tree after checking out.
+ */
+export type CleanPolicy = { mode: CleanMode; 
+/**
+ * gitignore-style patterns that are never cleaned, e.g. `config/local.json` or `.vscode/`.
+ */
+preserve: string[] }
+export type CloneCompleteEvent = { repo_name: string; success: boolean; cancelled: boolean; error_message: string | null }
+/**
+ * Options for `git clone`, kept on the repository so resumes, tag fetches
+ * and deepening reuse the same settings.
+ */
+export type CloneOptions = { depth: number | null; filterBlobs: boolean; singleBranch: boolean; branch: string | null; jobs: number | null; 
+/**
+ * Cone-mode sparse-checkout directories; empty means a full checkout.
+ */
+sparsePaths: string[]; 
+/**
+ * Private key for SSH remotes, passed to git through `GIT_SSH_COMMAND`.
+ */
+sshKeyPath: string | null }
+export type CloneProgressEvent = { repo_name: string; progress: number; message: string; receivedBytes: number | null; totalObjects: number | null; receivedObjects: number | null; speed: string | null; submodule: string | null }
+/**
+ * Error returned by commands that run git: either a classified git failure
+ * or a plain message for everything else (unknown repository, bad input, ...).
+ */
+export type CommandError = { kind: "git"; error: GitError } | { kind: "message"; message: string }
+export type CredentialKind = "https" | "ssh"
+/**
+ * Emitted when the remote turned git away for lack of credentials, so the frontend
+ * can ask for a token (HTTPS) or an SSH key and retry.
+ */
+export type CredentialRequestEvent = { repo_name: string; remote_url: string; host: string; kind: CredentialKind }
+/**
+ * A saved HTTPS credential as shown in settings; the token itself never leaves the backend.
+ */
+export type CredentialSummary = { host: string; username: string }
+export type FollowChannel = { channel: string; 
+/**
+ * Ignore the channel's branches and follow only its tags.
+ */
+tagsOnly: boolean }
+/**
+ * One switch follow mode made, or had to skip.
+ */
+export type FollowRecord = { 
+/**
+ * RFC 3339.
+ */
+at: string; 
+/**
+ * Display name of the version switched away from.
+ */
+from: string; 
+/**
+ * Display name of the version switched to.
+ */
+to: string; 
+/**
+ * The tag or `BRANCH:` entry of `to`.
+ */
+tag: string; 
+/**
+ * Why the switch was not made; `None` when it was.
+ */
+skipped: string | null }
+/**
+ * Which implementation runs git operations, chosen in settings.
+ */
+export type GitBackendKind = /**
+ * The git executable in PATH.
+ */
+"cli" | /**
+ * The built-in gix implementation; works without git installed.
+ */
+"gix"
+/**
+ * A failed git invocation, classified from its stderr so the frontend can
+ * show an actionable message instead of a generic failure.
+ */
+export type GitError = /**
+ * The remote wants credentials that are missing or were rejected.
+ */
+{ kind: "authRequired"; exit_code: number | null; stderr: string } | /**
+ * DNS lookup, connection or TLS handshake with the remote failed.
+ */
+{ kind: "hostUnreachable"; exit_code: number | null; stderr: string } | /**
+ * The remote answered but has no repository at that URL (or hides it from us).
+ */
+{ kind: "repoNotFound"; exit_code: number | null; stderr: string } | { kind: "diskFull"; exit_code: number | null; stderr: string } | /**
+ * The repository is owned by another user and is not listed in `safe.directory`.
+ */
+{ kind: "dubiousOwnership"; exit_code: number | null; stderr: string } | /**
+ * `.git/index.lock` exists, left behind by a crashed or still running git process.
+ */
+{ kind: "indexLocked"; exit_code: number | null; stderr: string } | /**
+ * Local modifications would be overwritten by the requested checkout.
+ */
+{ kind: "localChangesConflict"; exit_code: number | null; stderr: string } | /**
+ * The repository uses Git LFS but `git-lfs` is not installed.
+ */
+{ kind: "lfsMissing" } | /**
+ * git could not be started at all, e.g. it is not in PATH.
+ */
+{ kind: "spawnFailed"; message: string } | /**
+ * git ran longer than the command's timeout and was killed.
+ */
+{ kind: "timedOut"; seconds: number } | /**
+ * The selected backend cannot do what was asked, e.g. sparse checkout with gix.
+ */
+{ kind: "unsupported"; operation: string } | { kind: "cancelled" } | { kind: "other"; exit_code: number | null; stderr: string }
+/**
+ * The checked-out commit and the ref it was reached through.
+ */
+export type HeadInfo = { ref: HeadRef; commit: string; subject: string; 
+/**
+ * Committer date, RFC 3339.
+ */
+date: string }
+export type HeadRef = /**
+ * A local branch and the remote branch it tracks, e.g. `origin/main`.
+ */
+{ kind: "branch"; name: string; upstream: string | null } | /**
+ * A detached HEAD with a tag pointing at it.
+ */
+{ kind: "tag"; name: string } | /**
+ * Any other commit, named by `git describe` (`v1.2.0-3-gabc1234`) or its short hash.
+ */
+{ kind: "detached"; describe: string }
+/**
+ * One successful clone or version switch of a repository.
+ */
+export type HistoryEntry = { 
+/**
+ * RFC 3339.
+ */
+at: string; source: SwitchSource; 
+/**
+ * What was checked out before; `None` for a clone.
+ */
+from: HistoryPoint | null; to: HistoryPoint }
+export type HistoryPoint = { ref: HeadRef; commit: string; 
+/**
+ * How the version list showed it, e.g. `qa-1.2.0`.
+ */
+displayName: string }
+export type InterruptedClone = { name: string; remote_url: string | null; tempPath: string; startedAt: string | null; hasPartialData: boolean }
+/**
+ * Emitted when a refresh lists a tag or branch that was not there before, on a
+ * channel the repository watches (see `AutoRefresh::channels`).
+ */
+export type NewVersionAvailableEvent = { repo_id: number; repo_name: string; channel: string; tag: string; displayName: string }
+export type Pin = { 
+/**
+ * The tag or `BRANCH:` entry the repository is held on.
+ */
+tag: string; 
+/**
+ * The commit `tag` pointed at when pinned.
+ */
+commit: string; reason: string; 
+/**
+ * git's `user.name`, or the OS user when that is not set.
+ */
+pinnedBy: string; 
+/**
+ * RFC 3339.
+ */
+pinnedAt: string }
+/**
+ * How the pinned ref looks on origin, when it no longer matches the pin.
+ */
+export type PinDrift = /**
+ * Deleted on origin.
+ */
+{ kind: "missing" } | /**
+ * Points at another commit on origin.
+ */
+{ kind: "moved"; commit: string }
+/**
+ * How `refresh_all` fetches every repository, chosen in settings.
+ */
+export type RefreshSettings = { 
+/**
+ * Most repositories fetched at the same time.
+ */
+concurrency: number; 
+/**
+ * Refresh everything once when the app starts.
+ */
+onLaunch: boolean }
+export type RepositoryInfo = { id: number; name: string; remote_url: string; branch: string; path: string; gameVersion: string; gameVersions: string[]; server: string; serverOptions: string[]; hasWarning: boolean; lastSyncTime: string | null; cloneOptions: CloneOptions; 
+/**
+ * Replaces the global channel rules for this repository when set.
+ */
+channelRules: ChannelRule[] | null; tagSort: TagSortMode; 
+/**
+ * Details for each entry of `gameVersions`, `None` for branches.
+ */
+versionDetails: (TagDetails | null)[]; cleanPolicy: CleanPolicy; 
+/**
+ * What is actually checked out; `None` until the repository has been read.
+ */
+head: HeadInfo | null; 
+/**
+ * When the app last cloned or switched this checkout, RFC 3339.
+ */
+switchedAt: string | null; status: RepositoryStatus | null; autoRefresh: AutoRefresh; 
+/**
+ * Switches to the newest version of a channel whenever a refresh finds one.
+ */
+follow: FollowChannel | null; 
+/**
+ * Automatic switches made or skipped by `follow`, oldest first.
+ */
+followLog: FollowRecord[]; 
+/**
+ * Holds the repository on one version: switches are refused unless overridden.
+ */
+pin: Pin | null }
+/**
+ * Emitted by `refresh_all` and scheduled refreshes as each repository finishes,
+ * so the list can update before the slowest remote answers.
+ */
+export type RepositoryRefreshedEvent = { repo_id: number; repo_name: string; 
+/**
+ * The refreshed repository; `None` when the refresh failed.
+ */
+repository: RepositoryInfo | null; error: CommandError | null }
+/**
+ * Local state of a checkout, recomputed on refresh and after every switch.
+ */
+export type RepositoryStatus = { 
+/**
+ * Tracked files with changes in the working tree.
+ */
+modified: number; 
+/**
+ * Files with changes staged in the index.
+ */
+staged: number; untracked: number; 
+/**
+ * Commits on the checked-out branch that its upstream lacks; `None` off a tracking branch.
+ */
+ahead: number | null; 
+/**
+ * Commits on the upstream that the checked-out branch lacks; `None` off a tracking branch.
+ */
+behind: number | null; 
+/**
+ * Display names of listed tags created after `switchedAt`, in list order.
+ */
+newTags: string[]; 
+/**
+ * Set by a refresh when the pinned tag or branch changed on origin.
+ */
+pinDrift: PinDrift | null }
+/**
+ * How `change_version` treats local changes before resetting the checkout.
+ */
+export type SwitchMode = /**
+ * Throw local changes away.
+ */
+"discard" | /**
+ * Keep local changes and commits in a backup ref first, see `list_backups`.
+ */
+"backup"
+/**
+ * Everything a version switch would throw away, so it can be shown before confirming.
+ */
+export type SwitchPreflight = { 
+/**
+ * Tracked files changed in the working tree.
+ */
+modified: string[]; staged: string[]; 
+/**
+ * Untracked files the clean policy removes; directories are listed once with a
+ * trailing `/`. Empty when the policy keeps untracked files.
+ */
+untracked: string[]; 
+/**
+ * Ignored files the clean policy removes, only with `UntrackedAndIgnored`.
+ * Backups leave ignored files out, so these cannot be restored.
+ */
+ignored: string[]; 
+/**
+ * Tracked files marked assume-unchanged or skip-worktree, whose local edits
+ * git does not report but a reset still overwrites.
+ */
+ignoredTracked: string[]; 
+/**
+ * Commits in HEAD that no remote branch or tag contains.
+ */
+localCommits: ChangelogCommit[]; 
+/**
+ * Set when a list was cut short.
+ */
+truncated: boolean }
+export type SwitchSource = "clone" | /**
+ * `change_version`, picked by the user.
+ */
+"manual" | /**
+ * Follow mode.
+ */
+"automatic" | /**
+ * `rollback_version`.
+ */
+"rollback"
+/**
+ * The commit a tag points at and, for annotated tags, who tagged it and why.
+ * Dates are RFC 3339 with the author's offset.
+ */
+export type TagDetails = { commit: string; commitDate: string | null; tagDate: string | null; 
+/**
+ * `Name <email>`.
+ */
+tagger: string | null; 
+/**
+ * The annotated tag's message, e.g. release notes.
+ */
+message: string | null }
+export type TagInfo = { originalTag: string; displayName: string; channel: string; 
+/**
+ * The version taken from the name, e.g. `2.0.0-rc1`; used for version ordering.
+ */
+version: string | null; 
+/**
+ * Missing for branch entries.
+ */
+details: TagDetails | null }
+export type TagPage = { tags: TagInfo[]; 
+/**
+ * Number of entries matching the query, across all pages.
+ */
+total: number; hasMore: boolean }
+/**
+ * A search over every tag of a repository, beyond the short list kept on it.
+ */
+export type TagQuery = { 
+/**
+ * Case-insensitive text matched against tag and display names. A version like
+ * `1.4.7` also lists the tags with exactly that version first.
+ */
+search: string | null; channel: string | null; 
+/**
+ * Also list tags no channel rule matches, under an empty channel.
+ */
+includeAll: boolean; offset: number; limit: number }
+/**
+ * How the tags in a repository's version list are ordered, newest first.
+ */
+export type TagSortMode = /**
+ * By the version in the tag name, so re-tagging an old build does not move it up.
+ */
+"version" | /**
+ * By when the tag was created, or for lightweight tags when their commit was
+ * (`git tag --sort=-creatordate`).
+ */
+"creatorDate" | /**
+ * By when the tag was created; lightweight tags have no tagger date and come last.
+ */
+"taggerDate"
 
 /** tauri-specta globals **/
 